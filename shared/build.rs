@@ -0,0 +1,19 @@
+//! Records the compiler the crate was built with, so the host can refuse plugins whose egui
+//! types would not line up with its own. See `PluginDeclaration::build` in `src/plugin/abi`.
+use std::{env, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let rustc_version = Command::new(&rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_owned())
+        .unwrap_or_else(|| "rustc unknown".to_owned());
+
+    println!("cargo:rustc-env=ARKOMP_RUSTC_VERSION={}", rustc_version);
+}
@@ -0,0 +1,299 @@
+use crate::movement::{self, Easing};
+use serde::{Deserialize, Serialize};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const SIT_ANIMATION: &str = "Sit";
+pub const SLEEP_ANIMATION: &str = "Sleep";
+
+/// Tuning of the autonomous behaviour of an operator. Weights are relative to each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BehaviourConfig {
+    pub idle_weight: f32,
+    pub wander_weight: f32,
+    pub sit_weight: f32,
+    pub special_weight: f32,
+    /// Animations picked from at random for the special state.
+    pub special_animations: Vec<String>,
+    /// Range in seconds from which the time until the next decision is drawn.
+    pub decision_interval: (f32, f32),
+    /// Seconds without instructions from a client after which the operator falls asleep.
+    pub sleep_after: Option<f32>,
+    /// Maximum distance in pixels of a single wander.
+    pub wander_radius: f32,
+    pub wander_speed: f32,
+}
+
+impl Default for BehaviourConfig {
+    fn default() -> Self {
+        Self {
+            idle_weight: 4.0,
+            wander_weight: 3.0,
+            sit_weight: 1.0,
+            special_weight: 1.0,
+            special_animations: vec!["Special".to_owned()],
+            decision_interval: (4.0, 12.0),
+            sleep_after: Some(300.0),
+            wander_radius: 400.0,
+            wander_speed: 80.0,
+        }
+    }
+}
+
+impl BehaviourConfig {
+    /// Rejects values that would make the engine produce NaN positions or decide every frame.
+    pub fn validate(&self) -> Result<(), String> {
+        movement::check_speed(self.wander_speed).map_err(|e| format!("wander_{}", e))?;
+        if !(self.wander_radius.is_finite() && self.wander_radius >= 0.0) {
+            return Err(format!(
+                "wander_radius must be finite and not negative, got {}",
+                self.wander_radius
+            ));
+        }
+        let (min, max) = self.decision_interval;
+        if !(min.is_finite() && max.is_finite() && min >= 0.0 && max > 0.0 && min <= max) {
+            return Err(format!(
+                "decision_interval must be a finite range with 0 <= min <= max and max > 0, \
+                 got ({}, {})",
+                min, max
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BehaviourState {
+    Idle,
+    Wander,
+    Sit,
+    Sleep,
+    Special,
+}
+
+/// What the behaviour engine wants the operator to do next.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Idle,
+    Wander {
+        target: (f32, f32),
+        speed: f32,
+        easing: Easing,
+    },
+    Sit,
+    Sleep,
+    Special(String),
+}
+
+#[derive(Debug)]
+pub struct Behaviour {
+    config: BehaviourConfig,
+    state: BehaviourState,
+    until_decision: f32,
+    inactive: f32,
+    rng: Rng,
+}
+
+impl Behaviour {
+    pub fn new(config: BehaviourConfig, seed: &str) -> Self {
+        let mut rng = Rng::new(seed);
+        let until_decision = rng.range(config.decision_interval);
+        Self {
+            config,
+            state: BehaviourState::Idle,
+            until_decision,
+            inactive: 0.0,
+            rng,
+        }
+    }
+
+    pub fn config(&self) -> &BehaviourConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> BehaviourState {
+        self.state
+    }
+
+    /// Records instructions from a client: resets the sleep timer and postpones the next
+    /// autonomous decision so the instruction is not immediately overridden.
+    pub fn touch(&mut self) {
+        self.inactive = 0.0;
+        self.state = BehaviourState::Idle;
+        self.until_decision = self.rng.range(self.config.decision_interval);
+    }
+
+    /// Advances the timers by `dt` seconds. `busy` operators (e.g. still walking) are not
+    /// interrupted; wander targets are kept inside `bounds` (min, max).
+    pub fn tick(
+        &mut self,
+        dt: f32,
+        position: (f32, f32),
+        bounds: ((f32, f32), (f32, f32)),
+        busy: bool,
+    ) -> Option<Action> {
+        self.inactive += dt;
+        if self.state == BehaviourState::Sleep {
+            return None;
+        }
+        if self
+            .config
+            .sleep_after
+            .is_some_and(|after| self.inactive >= after)
+            && !busy
+        {
+            self.state = BehaviourState::Sleep;
+            return Some(Action::Sleep);
+        }
+
+        self.until_decision -= dt;
+        if self.until_decision > 0.0 || busy {
+            return None;
+        }
+        self.until_decision = self.rng.range(self.config.decision_interval);
+
+        let special = !self.config.special_animations.is_empty();
+        let weights = [
+            self.config.idle_weight,
+            self.config.wander_weight,
+            self.config.sit_weight,
+            if special {
+                self.config.special_weight
+            } else {
+                0.0
+            },
+        ];
+        let (state, action) = match self.rng.weighted(&weights)? {
+            0 => (BehaviourState::Idle, Action::Idle),
+            1 => {
+                let radius = self.config.wander_radius;
+                let x = position.0 + self.rng.range((-radius, radius));
+                let y = position.1 + self.rng.range((-radius, radius)) * 0.25;
+                (
+                    BehaviourState::Wander,
+                    Action::Wander {
+                        target: (
+                            x.clamp(bounds.0.0, bounds.1.0),
+                            y.clamp(bounds.0.1, bounds.1.1),
+                        ),
+                        speed: self.config.wander_speed,
+                        easing: Easing::EaseInOut,
+                    },
+                )
+            }
+            2 => (BehaviourState::Sit, Action::Sit),
+            _ => {
+                let idx = self.rng.below(self.config.special_animations.len());
+                (
+                    BehaviourState::Special,
+                    Action::Special(self.config.special_animations[idx].clone()),
+                )
+            }
+        };
+        if state == self.state && state != BehaviourState::Wander {
+            return None;
+        }
+        self.state = state;
+        Some(action)
+    }
+}
+
+/// Small xorshift generator; behaviour only needs cheap, non-reproducible randomness.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        Self(hasher.finish() | 1)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next_f32() * n as f32) as usize).min(n.saturating_sub(1))
+    }
+
+    fn weighted(&mut self, weights: &[f32]) -> Option<usize> {
+        let total: f32 = weights.iter().map(|w| w.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = self.next_f32() * total;
+        for (idx, weight) in weights.iter().enumerate() {
+            pick -= weight.max(0.0);
+            if pick < 0.0 {
+                return Some(idx);
+            }
+        }
+        weights.iter().rposition(|w| *w > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(BehaviourConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_wander_radius_that_is_not_finite_or_negative() {
+        for wander_radius in [f32::INFINITY, f32::NAN, -1.0] {
+            let config = BehaviourConfig {
+                wander_radius,
+                ..BehaviourConfig::default()
+            };
+            let error = config.validate().unwrap_err();
+            assert!(error.starts_with("wander_radius"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn rejects_decision_interval_that_is_not_a_valid_range() {
+        for decision_interval in [
+            (f32::NAN, 1.0),
+            (1.0, f32::INFINITY),
+            (-1.0, 1.0),
+            (5.0, 2.0),
+            (0.0, 0.0),
+        ] {
+            let config = BehaviourConfig {
+                decision_interval,
+                ..BehaviourConfig::default()
+            };
+            let error = config.validate().unwrap_err();
+            assert!(error.starts_with("decision_interval"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn accepts_a_fixed_decision_interval_and_zero_radius() {
+        let config = BehaviourConfig {
+            decision_interval: (3.0, 3.0),
+            wander_radius: 0.0,
+            ..BehaviourConfig::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// IPC commands added by a command plugin. The host creates one instance per loaded plugin
+/// and routes requests for the commands it declares to it.
+pub trait CommandProvider: std::fmt::Debug + Send + Sync {
+    /// The commands this plugin handles. Read once, when the plugin is loaded.
+    fn commands(&self) -> Vec<CommandSpec>;
+    /// Executes `command` with the arguments sent by the client, already checked against its
+    /// schema. The returned value is sent back as the response payload; an error becomes an
+    /// error response.
+    fn execute(&mut self, command: &str, args: Value) -> Result<Value, String>;
+}
+
+/// A command as declared by a plugin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandSpec {
+    /// The `command` tag clients send. Must not collide with a built-in or another plugin's
+    /// command.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON schema of the arguments, i.e. the request without its `command` and `request_id`.
+    /// `null` accepts anything.
+    #[serde(default)]
+    pub schema: Value,
+}
+
+impl CommandSpec {
+    pub fn new(name: impl Into<String>, schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            schema,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Checks `args` against the schema. Supports the `type`, `enum`, `properties`,
+    /// `required`, `additionalProperties`, `items`, `minimum` and `maximum` keywords; others
+    /// are ignored.
+    pub fn validate(&self, args: &Value) -> Result<(), String> {
+        check(&self.schema, args, "args")
+    }
+}
+
+fn check(schema: &Value, value: &Value, at: &str) -> Result<(), String> {
+    let Value::Object(schema) = schema else {
+        return match schema {
+            Value::Bool(false) => Err(format!("{} is not allowed", at)),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Array(tys) => tys.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.iter().any(|ty| has_type(value, ty)) {
+            return Err(format!("{} must be of type {}", at, types.join(" or ")));
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum")
+        && !options.contains(value)
+    {
+        return Err(format!(
+            "{} must be one of {}",
+            at,
+            Value::Array(options.clone())
+        ));
+    }
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+            && n < min
+        {
+            return Err(format!("{} must be at least {}", at, min));
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+            && n > max
+        {
+            return Err(format!("{} must be at most {}", at, max));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for field in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(field) {
+                    return Err(format!("{}.{} is required", at, field));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (field, value) in object {
+            let at = format!("{}.{}", at, field);
+            match properties.and_then(|p| p.get(field)) {
+                Some(property) => check(property, value, &at)?,
+                None => {
+                    if let Some(additional) = schema.get("additionalProperties") {
+                        check(additional, value, &at)?;
+                    }
+                }
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(item_schema, item, &format!("{}[{}]", at, i))?;
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(schema: Value, args: Value) -> Result<(), String> {
+        CommandSpec::new("Test", schema).validate(&args)
+    }
+
+    /// Schema of `{"target": {"name": ..., "volume": ..., "tags": [...]}}`.
+    fn nested() -> Value {
+        json!({
+            "type": "object",
+            "required": ["target"],
+            "properties": {
+                "target": {
+                    "type": "object",
+                    "required": ["name"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "name": {"type": "string"},
+                        "mode": {"enum": ["loop", "once"]},
+                        "volume": {"type": "number", "minimum": 0, "maximum": 1},
+                        "tags": {"type": "array", "items": {"type": "string"}},
+                    },
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn accepts_valid_args() {
+        let args =
+            json!({"target": {"name": "rain", "mode": "loop", "volume": 0.5, "tags": ["a"]}});
+        assert_eq!(validate(nested(), args), Ok(()));
+    }
+
+    #[test]
+    fn null_schema_accepts_anything() {
+        assert_eq!(validate(Value::Null, json!({"anything": [1, 2]})), Ok(()));
+    }
+
+    #[test]
+    fn rejects_wrong_type() {
+        let args = json!({"target": {"name": 3}});
+        assert_eq!(
+            validate(nested(), args),
+            Err("args.target.name must be of type string".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_value_outside_enum() {
+        let args = json!({"target": {"name": "rain", "mode": "twice"}});
+        assert_eq!(
+            validate(nested(), args),
+            Err(r#"args.target.mode must be one of ["loop","once"]"#.to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_missing_required_field() {
+        assert_eq!(
+            validate(nested(), json!({"target": {}})),
+            Err("args.target.name is required".to_owned())
+        );
+        assert_eq!(
+            validate(nested(), json!({})),
+            Err("args.target is required".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_additional_properties() {
+        let args = json!({"target": {"name": "rain", "colour": "grey"}});
+        assert_eq!(
+            validate(nested(), args),
+            Err("args.target.colour is not allowed".to_owned())
+        );
+    }
+
+    #[test]
+    fn checks_additional_properties_against_their_schema() {
+        let schema = json!({"type": "object", "additionalProperties": {"type": "integer"}});
+        assert_eq!(validate(schema.clone(), json!({"a": 1})), Ok(()));
+        assert_eq!(
+            validate(schema, json!({"a": 1.5})),
+            Err("args.a must be of type integer".to_owned())
+        );
+    }
+
+    #[test]
+    fn checks_each_item() {
+        let args = json!({"target": {"name": "rain", "tags": ["a", 2]}});
+        assert_eq!(
+            validate(nested(), args),
+            Err("args.target.tags[1] must be of type string".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_numbers_out_of_range() {
+        assert_eq!(
+            validate(
+                nested(),
+                json!({"target": {"name": "rain", "volume": -0.5}})
+            ),
+            Err("args.target.volume must be at least 0".to_owned())
+        );
+        assert_eq!(
+            validate(nested(), json!({"target": {"name": "rain", "volume": 1.5}})),
+            Err("args.target.volume must be at most 1".to_owned())
+        );
+        assert_eq!(
+            validate(nested(), json!({"target": {"name": "rain", "volume": 1}})),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn accepts_any_of_several_types() {
+        let schema = json!({"type": ["string", "null"]});
+        assert_eq!(validate(schema.clone(), Value::Null), Ok(()));
+        assert_eq!(
+            validate(schema, json!(1)),
+            Err("args must be of type string or null".to_owned())
+        );
+    }
+}
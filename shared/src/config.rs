@@ -0,0 +1,406 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tracing::level_filters::LevelFilter;
+
+pub const DEFAULT_CONFIG_PATH: &str = "arkomp.ron";
+
+pub const USAGE: &str = "\
+Usage: arkomp [OPTIONS]
+
+Options:
+  --config <PATH>        configuration file [env: ARKOMP_CONFIG] [default: arkomp.ron]
+  --listen <ADDR>        WebSocket listen address [env: ARKOMP_LISTEN]
+  --log-dir <DIR>        log file directory [env: ARKOMP_LOG_DIR]
+  --log-level <LEVEL>    log file level [env: ARKOMP_LOG_LEVEL]
+  --stdout-level <LEVEL> console log level [env: ARKOMP_STDOUT_LEVEL]
+  --window-mode <MODE>   fullscreen, maximized or windowed [env: ARKOMP_WINDOW_MODE]
+  --plugin-dir <DIR>     plugin search directory, repeatable [env: ARKOMP_PLUGIN_DIRS]
+  --hot-reload [<BOOL>]  reload plugins when their library changes [env: ARKOMP_HOT_RELOAD]
+  -h, --help             print this help";
+
+/// Host configuration, read from a RON file and overridden by environment and CLI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostConfig {
+    pub listen: String,
+    pub log: LogConfig,
+    pub window: WindowConfig,
+    /// Directories searched for plugin libraries given by relative path.
+    pub plugin_dirs: Vec<PathBuf>,
+    /// Reload plugins and respawn their operators when a library file changes.
+    pub hot_reload: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub dir: PathBuf,
+    pub file_level: String,
+    pub stdout_level: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WindowMode {
+    Fullscreen,
+    Maximized,
+    Windowed { width: f32, height: f32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub mode: WindowMode,
+    pub always_on_top: bool,
+    pub mouse_passthrough: bool,
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        Self {
+            listen: "127.0.0.1:2887".to_owned(),
+            log: LogConfig::default(),
+            window: WindowConfig::default(),
+            plugin_dirs: vec![PathBuf::from("./plugins/")],
+            hot_reload: false,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("./logs/"),
+            file_level: "debug".to_owned(),
+            stdout_level: "info".to_owned(),
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            mode: WindowMode::Fullscreen,
+            always_on_top: true,
+            mouse_passthrough: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    HelpRequested,
+    Usage(String),
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, String),
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::HelpRequested => write!(f, "{}", USAGE),
+            Error::Usage(e) => write!(f, "{}\n\n{}", e, USAGE),
+            Error::Io(path, e) => write!(f, "Cannot read config {}: {}", path.display(), e),
+            Error::Parse(path, e) => write!(f, "Malformed config {}: {}", path.display(), e),
+            Error::Invalid(e) => write!(f, "Invalid configuration: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Command line flags; everything set here wins over the environment and the config file.
+#[derive(Debug, Default)]
+struct Overrides {
+    config: Option<PathBuf>,
+    listen: Option<String>,
+    log_dir: Option<PathBuf>,
+    log_level: Option<String>,
+    stdout_level: Option<String>,
+    window_mode: Option<String>,
+    plugin_dirs: Vec<PathBuf>,
+    hot_reload: Option<String>,
+}
+
+impl Overrides {
+    fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+        let mut overrides = Self::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_owned(), Some(value.to_owned())),
+                None => (arg, None),
+            };
+            if flag == "-h" || flag == "--help" {
+                return Err(Error::HelpRequested);
+            }
+            if flag == "--hot-reload" {
+                // The value is optional, so a following token is only taken if it is a bool.
+                let value = inline
+                    .or_else(|| args.next_if(|next| parse_bool(next).is_some()))
+                    .unwrap_or_else(|| "true".to_owned());
+                overrides.hot_reload = Some(value);
+                continue;
+            }
+            let value = match inline.or_else(|| args.next()) {
+                Some(value) => value,
+                None => return Err(Error::Usage(format!("{} requires a value", flag))),
+            };
+            match flag.as_str() {
+                "--config" => overrides.config = Some(value.into()),
+                "--listen" => overrides.listen = Some(value),
+                "--log-dir" => overrides.log_dir = Some(value.into()),
+                "--log-level" => overrides.log_level = Some(value),
+                "--stdout-level" => overrides.stdout_level = Some(value),
+                "--window-mode" => overrides.window_mode = Some(value),
+                "--plugin-dir" => overrides.plugin_dirs.push(value.into()),
+                _ => return Err(Error::Usage(format!("unknown option {}", flag))),
+            }
+        }
+        Ok(overrides)
+    }
+
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Self {
+            config: var("ARKOMP_CONFIG").map(PathBuf::from),
+            listen: var("ARKOMP_LISTEN"),
+            log_dir: var("ARKOMP_LOG_DIR").map(PathBuf::from),
+            log_level: var("ARKOMP_LOG_LEVEL"),
+            stdout_level: var("ARKOMP_STDOUT_LEVEL"),
+            window_mode: var("ARKOMP_WINDOW_MODE"),
+            plugin_dirs: std::env::var_os("ARKOMP_PLUGIN_DIRS")
+                .map(|dirs| std::env::split_paths(&dirs).collect())
+                .unwrap_or_default(),
+            hot_reload: var("ARKOMP_HOT_RELOAD"),
+        }
+    }
+
+    fn apply(self, config: &mut HostConfig) -> Result<(), Error> {
+        if let Some(listen) = self.listen {
+            config.listen = listen;
+        }
+        if let Some(dir) = self.log_dir {
+            config.log.dir = dir;
+        }
+        if let Some(level) = self.log_level {
+            config.log.file_level = level;
+        }
+        if let Some(level) = self.stdout_level {
+            config.log.stdout_level = level;
+        }
+        if let Some(mode) = self.window_mode {
+            config.window.mode = match mode.to_ascii_lowercase().as_str() {
+                "fullscreen" => WindowMode::Fullscreen,
+                "maximized" => WindowMode::Maximized,
+                "windowed" => match config.window.mode {
+                    mode @ WindowMode::Windowed { .. } => mode,
+                    _ => WindowMode::Windowed {
+                        width: 1280.0,
+                        height: 720.0,
+                    },
+                },
+                _ => {
+                    return Err(Error::Invalid(format!(
+                        "window mode {:?} is not one of fullscreen, maximized, windowed",
+                        mode
+                    )));
+                }
+            };
+        }
+        if !self.plugin_dirs.is_empty() {
+            config.plugin_dirs = self.plugin_dirs;
+        }
+        if let Some(hot_reload) = self.hot_reload {
+            config.hot_reload = parse_bool(&hot_reload).ok_or_else(|| {
+                Error::Invalid(format!("hot reload {:?} is not a boolean", hot_reload))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+impl HostConfig {
+    /// Builds the configuration from defaults, the config file, `ARKOMP_*` environment
+    /// variables and command line arguments (without the program name), in that order.
+    pub fn load(args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+        let cli = Overrides::from_args(args)?;
+        let env = Overrides::from_env();
+
+        let mut config = match cli.config.as_ref().or(env.config.as_ref()) {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+        env.apply(&mut config)?;
+        cli.apply(&mut config)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        ron::from_str(&content).map_err(|e| Error::Parse(path.to_path_buf(), e.to_string()))
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.listen_addr()?;
+        self.log.file_level()?;
+        self.log.stdout_level()?;
+        if let WindowMode::Windowed { width, height } = self.window.mode
+            && !(width > 0.0 && height > 0.0)
+        {
+            return Err(Error::Invalid(format!(
+                "window size {}x{} must be positive",
+                width, height
+            )));
+        }
+        if let Some(dir) = self.plugin_dirs.iter().find(|dir| dir.is_file()) {
+            return Err(Error::Invalid(format!(
+                "plugin directory {} is a file",
+                dir.display()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn listen_addr(&self) -> Result<SocketAddr, Error> {
+        self.listen
+            .parse()
+            .map_err(|e| Error::Invalid(format!("listen address {:?}: {}", self.listen, e)))
+    }
+}
+
+impl LogConfig {
+    pub fn file_level(&self) -> Result<LevelFilter, Error> {
+        parse_level(&self.file_level)
+    }
+
+    pub fn stdout_level(&self) -> Result<LevelFilter, Error> {
+        parse_level(&self.stdout_level)
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, Error> {
+    LevelFilter::from_str(level).map_err(|_| {
+        Error::Invalid(format!(
+            "log level {:?} is not one of off, error, warn, info, debug, trace",
+            level
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Overrides, Error> {
+        Overrides::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn hot_reload(flags: &[&str]) -> Option<String> {
+        args(flags).unwrap().hot_reload
+    }
+
+    #[test]
+    fn hot_reload_flag_forms() {
+        assert_eq!(hot_reload(&[]), None);
+        assert_eq!(hot_reload(&["--hot-reload"]).as_deref(), Some("true"));
+        assert_eq!(
+            hot_reload(&["--hot-reload=false"]).as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            hot_reload(&["--hot-reload", "false"]).as_deref(),
+            Some("false")
+        );
+        assert_eq!(hot_reload(&["--hot-reload", "On"]).as_deref(), Some("On"));
+    }
+
+    #[test]
+    fn hot_reload_without_value_leaves_the_next_flag_alone() {
+        let overrides = args(&["--hot-reload", "--listen", "0.0.0.0:1"]).unwrap();
+        assert_eq!(overrides.hot_reload.as_deref(), Some("true"));
+        assert_eq!(overrides.listen.as_deref(), Some("0.0.0.0:1"));
+    }
+
+    #[test]
+    fn value_flags_take_a_separate_or_inline_value() {
+        let overrides = args(&[
+            "--listen",
+            "0.0.0.0:1",
+            "--log-level=warn",
+            "--plugin-dir",
+            "a",
+            "--plugin-dir=b",
+        ])
+        .unwrap();
+        assert_eq!(overrides.listen.as_deref(), Some("0.0.0.0:1"));
+        assert_eq!(overrides.log_level.as_deref(), Some("warn"));
+        assert_eq!(
+            overrides.plugin_dirs,
+            [PathBuf::from("a"), PathBuf::from("b")]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(matches!(args(&["--listen"]), Err(Error::Usage(_))));
+        assert!(matches!(args(&["--bogus", "1"]), Err(Error::Usage(_))));
+        assert!(matches!(args(&["--help"]), Err(Error::HelpRequested)));
+        assert!(matches!(
+            args(&["--hot-reload=maybe"])
+                .unwrap()
+                .apply(&mut HostConfig::default()),
+            Err(Error::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn cli_wins_over_env_and_env_over_file() {
+        let mut config: HostConfig = ron::from_str(
+            r#"(listen: "10.0.0.1:1", log: (file_level: "trace"), hot_reload: true)"#,
+        )
+        .unwrap();
+        let env = Overrides {
+            listen: Some("10.0.0.2:2".to_owned()),
+            log_level: Some("warn".to_owned()),
+            plugin_dirs: vec!["env".into()],
+            ..Overrides::default()
+        };
+        let cli = args(&["--listen", "10.0.0.3:3", "--hot-reload", "false"]).unwrap();
+        env.apply(&mut config).unwrap();
+        cli.apply(&mut config).unwrap();
+
+        assert_eq!(config.listen, "10.0.0.3:3");
+        assert_eq!(config.log.file_level, "warn");
+        assert_eq!(config.log.stdout_level, "info");
+        assert_eq!(config.plugin_dirs, [PathBuf::from("env")]);
+        assert!(!config.hot_reload);
+    }
+
+    #[test]
+    fn unset_overrides_keep_the_file_values() {
+        let mut config: HostConfig = ron::from_str(r#"(hot_reload: true)"#).unwrap();
+        Overrides::default().apply(&mut config).unwrap();
+        assert!(config.hot_reload);
+        assert_eq!(config.plugin_dirs, [PathBuf::from("./plugins/")]);
+    }
+}
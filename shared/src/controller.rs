@@ -0,0 +1,12 @@
+use crate::ipc::{commands::Command, notification::Notification};
+
+/// Headless logic provided by a controller plugin. It observes what happens on the host and
+/// drives it by issuing commands, like an IPC client running inside the host. The host creates
+/// one instance per loaded plugin.
+pub trait Controller: std::fmt::Debug + Send + Sync {
+    /// Called for every notification the host emits.
+    fn notify(&mut self, notification: Notification);
+    /// Called periodically with the seconds since the last tick. The returned commands are
+    /// executed in order.
+    fn tick(&mut self, dt: f32) -> Vec<Command>;
+}
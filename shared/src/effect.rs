@@ -0,0 +1,10 @@
+/// Scene-wide visual provided by an effect plugin, e.g. a background, particles or weather.
+/// The host creates one instance per loaded plugin.
+pub trait Effect: std::fmt::Debug + Send + Sync {
+    /// Advances the effect by `dt` seconds. Called once per frame before rendering.
+    fn update(&mut self, dt: f32);
+    /// Paints behind the operators.
+    fn render_background(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui);
+    /// Paints over the operators.
+    fn render_foreground(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui);
+}
@@ -1,16 +1,59 @@
+use crate::{
+    movement::{self, Easing},
+    operator::OperatorRegistry,
+    plugin::{PluginKind, PluginRegistry},
+};
 use serde::{Deserialize, Serialize};
+use std::sync::mpsc::Sender;
+
+/// Channel events are sent to operators through.
+pub type EventSender = Sender<PendingEvent>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum Event {
-    Retreat { op_id: String },
-    SetSkin { op_id: String, skin: String },
-    SetAnimation { op_id: String, ani: String },
-    MoveTo { op_id: String, pos: (f32, f32) },
-    Sleep { op_id: String },
-    Sit { op_id: String },
+    Retreat {
+        op_id: String,
+    },
+    SetSkin {
+        op_id: String,
+        skin: String,
+    },
+    SetAnimation {
+        op_id: String,
+        ani: String,
+        #[serde(flatten)]
+        options: AnimationOptions,
+        /// Animations queued after `ani`, each on its own track and after the one before
+        /// it on that track.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        then: Vec<QueuedAnimation>,
+    },
+    MoveTo {
+        op_id: String,
+        pos: (f32, f32),
+        /// Pixels per second, defaults to `movement::DEFAULT_SPEED`.
+        #[serde(default)]
+        speed: Option<f32>,
+        #[serde(default)]
+        easing: Easing,
+    },
+    Sleep {
+        op_id: String,
+    },
+    Sit {
+        op_id: String,
+    },
 
-    CustomEvent { op_id: String, payload: String },
+    /// An event only the target operator's plugin understands. The plugin must list `name`
+    /// in the `custom_events` of its manifest, or the host rejects the event.
+    CustomEvent {
+        op_id: String,
+        /// Namespaced as `namespace.event`, e.g. `weather.rain`.
+        name: String,
+        #[serde(default)]
+        payload: serde_json::Value,
+    },
 }
 
 impl Event {
@@ -25,4 +68,233 @@ impl Event {
             Event::CustomEvent { op_id, .. } => op_id,
         }
     }
+
+    /// Name of the variant, as used by subscription filters.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Retreat { .. } => "Retreat",
+            Event::SetSkin { .. } => "SetSkin",
+            Event::SetAnimation { .. } => "SetAnimation",
+            Event::MoveTo { .. } => "MoveTo",
+            Event::Sleep { .. } => "Sleep",
+            Event::Sit { .. } => "Sit",
+            Event::CustomEvent { .. } => "CustomEvent",
+        }
+    }
+}
+
+/// How an animation is played. Every field is optional; the defaults replace whatever plays on
+/// track 0 with the animation, looping at normal speed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationOptions {
+    /// Higher tracks are applied on top of lower ones.
+    #[serde(default)]
+    pub track: usize,
+    #[serde(default = "default_looping")]
+    pub looping: bool,
+    /// Plays the animation this many times and then stops, instead of `looping`.
+    #[serde(default)]
+    pub loop_count: Option<u32>,
+    /// Starts once the animation currently on the track completes instead of replacing it.
+    #[serde(default)]
+    pub queue: bool,
+    /// Seconds to crossfade from the previous animation; the skeleton's default if omitted.
+    #[serde(default)]
+    pub mix: Option<f32>,
+    /// Playback speed, `1.0` being normal.
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            track: 0,
+            looping: default_looping(),
+            loop_count: None,
+            queue: false,
+            mix: None,
+            speed: default_speed(),
+        }
+    }
+}
+
+impl AnimationOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.speed.is_finite() || self.speed < 0.0 {
+            return Err(format!(
+                "speed must be zero or positive, got {}",
+                self.speed
+            ));
+        }
+        if let Some(mix) = self.mix.filter(|mix| !mix.is_finite() || *mix < 0.0) {
+            return Err(format!("mix must be zero or positive, got {}", mix));
+        }
+        if self.loop_count == Some(0) {
+            return Err("loop_count must be at least 1".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// An animation that follows another, see [`Event::SetAnimation`]. Its `queue` option is
+/// implied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedAnimation {
+    pub ani: String,
+    #[serde(flatten)]
+    pub options: AnimationOptions,
+}
+
+/// Something that happened in an operator's animation, reported to IPC clients so they can
+/// sync sounds or captions to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationEvent {
+    pub track: usize,
+    pub animation: String,
+    #[serde(flatten)]
+    pub kind: AnimationEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnimationEventKind {
+    Start,
+    /// Another animation was set or queued to replace this one.
+    Interrupt,
+    /// The animation will not be applied anymore.
+    End,
+    /// A loop, or the single play of a non-looping animation, finished.
+    Complete,
+    /// An event keyed in the animation.
+    Event {
+        name: String,
+        int: i32,
+        float: f32,
+        string: String,
+    },
+}
+
+/// What became of an event sent to an operator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Delivery {
+    Delivered,
+    OperatorNotFound,
+    /// Refused by the host or by the operator's plugin.
+    Rejected {
+        reason: String,
+    },
+}
+
+/// An event on its way to an operator. If `reply` is set, the outcome is sent back on it.
+#[derive(Debug)]
+pub struct PendingEvent {
+    pub event: Event,
+    pub reply: Option<Sender<Delivery>>,
+}
+
+impl PendingEvent {
+    pub fn new(event: Event) -> Self {
+        Self { event, reply: None }
+    }
+
+    pub fn with_reply(event: Event, reply: Sender<Delivery>) -> Self {
+        Self {
+            event,
+            reply: Some(reply),
+        }
+    }
+}
+
+impl Event {
+    /// Checks that a custom event is namespaced and accepted by the plugin of the operator it
+    /// targets, that a skin set on a Spine operator is one of its skins and that walk and
+    /// animation options are in range. Other events, and events for operators that do not
+    /// exist, pass.
+    pub fn check_accepted(
+        &self,
+        plugins: &PluginRegistry,
+        operators: &OperatorRegistry,
+    ) -> Result<(), String> {
+        if let Event::MoveTo { pos, speed, .. } = self {
+            movement::check_position(*pos)?;
+            return speed.map_or(Ok(()), movement::check_speed);
+        }
+        if let Event::SetSkin { op_id, skin } = self {
+            return check_skin(plugins, operators, op_id, skin);
+        }
+        if let Event::SetAnimation { options, then, .. } = self {
+            return std::iter::once(options)
+                .chain(then.iter().map(|queued| &queued.options))
+                .try_for_each(AnimationOptions::validate);
+        }
+        let Event::CustomEvent { op_id, name, .. } = self else {
+            return Ok(());
+        };
+        if !is_namespaced(name) {
+            return Err(format!(
+                "custom event {:?} is not namespaced as namespace.event",
+                name
+            ));
+        }
+        let Some(op) = operators.get(op_id) else {
+            return Ok(());
+        };
+        let plugin = &op.state().plugin;
+        match plugins.get_plugin(plugin) {
+            Ok(p) if p.manifest().accepts_event(name) => Ok(()),
+            Ok(_) => Err(format!(
+                "operator {} (plugin {}) does not accept custom event {}",
+                op_id, plugin, name
+            )),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Skins of Spine operators are managed by the host, which knows them from the manifest.
+/// Operators of other plugins decide for themselves.
+fn check_skin(
+    plugins: &PluginRegistry,
+    operators: &OperatorRegistry,
+    op_id: &str,
+    skin: &str,
+) -> Result<(), String> {
+    let Some(op) = operators.get(op_id) else {
+        return Ok(());
+    };
+    let Ok(plugin) = plugins.get_plugin(&op.state().plugin) else {
+        return Ok(());
+    };
+    let skins = &plugin.manifest().skins;
+    if plugin.kind() != PluginKind::Spine || skins.iter().any(|s| s == skin) {
+        return Ok(());
+    }
+    Err(format!(
+        "operator {} has no skin {}, expected one of {}",
+        op_id,
+        skin,
+        skins.join(", ")
+    ))
+}
+
+/// Whether `name` is a valid custom event name: at least two non-empty segments joined by
+/// dots, made of ASCII letters, digits, `_` and `-`.
+pub fn is_namespaced(name: &str) -> bool {
+    let mut segments = name.split('.');
+    let valid = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    segments.clone().count() >= 2 && segments.all(valid)
 }
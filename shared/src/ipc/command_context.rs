@@ -1,26 +1,43 @@
-use crate::{events::Event, operator::Operator, plugin::PluginRegistry};
-use std::sync::mpsc::Sender;
-use std::{collections::HashMap, sync::Arc};
+use crate::ipc::notification::{Notification, NotificationSender, Subscriptions};
+use crate::{
+    events::EventSender, operator::OperatorRegistry, plugin::PluginRegistry, scheduler::Scheduler,
+};
+use std::sync::Arc;
+use std::sync::Mutex;
 pub struct CommandContext {
-    operators: Arc<std::sync::RwLock<HashMap<String, Box<dyn Operator>>>>,
+    operators: Arc<std::sync::RwLock<OperatorRegistry>>,
     plugin_registry: Arc<std::sync::RwLock<PluginRegistry>>,
-    operator_tx: Sender<Event>,
+    operator_tx: EventSender,
+    notifier: NotificationSender,
+    scheduler: Arc<Mutex<Scheduler>>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
 }
 
 impl CommandContext {
     pub fn new(
-        operators: Arc<std::sync::RwLock<HashMap<String, Box<dyn Operator>>>>,
+        operators: Arc<std::sync::RwLock<OperatorRegistry>>,
         plugin_registry: Arc<std::sync::RwLock<PluginRegistry>>,
-        operator_tx: Sender<Event>,
+        operator_tx: EventSender,
+        notifier: NotificationSender,
+        scheduler: Arc<Mutex<Scheduler>>,
     ) -> Self {
         Self {
             operators,
             plugin_registry,
             operator_tx,
+            notifier,
+            scheduler,
+            subscriptions: Arc::default(),
         }
     }
 
-    pub fn operators(&mut self) -> &Arc<std::sync::RwLock<HashMap<String, Box<dyn Operator>>>> {
+    /// Binds the context to the subscriptions of the connection the command arrived on.
+    pub fn with_subscriptions(mut self, subscriptions: Arc<Mutex<Subscriptions>>) -> Self {
+        self.subscriptions = subscriptions;
+        self
+    }
+
+    pub fn operators(&mut self) -> &Arc<std::sync::RwLock<OperatorRegistry>> {
         &self.operators
     }
 
@@ -28,7 +45,20 @@ impl CommandContext {
         &self.plugin_registry
     }
 
-    pub fn sender(&self) -> std::sync::mpsc::Sender<Event> {
+    pub fn sender(&self) -> EventSender {
         self.operator_tx.clone()
     }
+
+    pub fn scheduler(&mut self) -> &Arc<Mutex<Scheduler>> {
+        &self.scheduler
+    }
+
+    pub fn subscriptions(&mut self) -> &Arc<Mutex<Subscriptions>> {
+        &self.subscriptions
+    }
+
+    /// Pushes a notification to all connections; it is dropped if nobody is listening.
+    pub fn notify(&self, notification: Notification) {
+        let _ = self.notifier.send(notification);
+    }
 }
@@ -0,0 +1,27 @@
+use crate::ipc::{
+    Payload,
+    command_context::CommandContext,
+    commands::{Error, ExecCommand, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelScheduledCommand {
+    schedule_id: u64,
+}
+
+impl ExecCommand for CancelScheduledCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        match ctx.scheduler().lock().unwrap().cancel(self.schedule_id) {
+            Some(scheduled) => Response::with_payload(
+                format!("Cancelled scheduled event {}", self.schedule_id),
+                Payload::ScheduleCancelled { scheduled },
+            ),
+            None => Error::ScheduleNotFound(format!(
+                "scheduled event {} does not exist",
+                self.schedule_id
+            ))
+            .into(),
+        }
+    }
+}
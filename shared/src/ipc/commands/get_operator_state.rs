@@ -0,0 +1,25 @@
+use crate::ipc::{
+    Payload,
+    command_context::CommandContext,
+    commands::{Error, ExecCommand, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetOperatorStateCommand {
+    id: String,
+}
+
+impl ExecCommand for GetOperatorStateCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        match ctx.operators().read().unwrap().get(&self.id) {
+            Some(op) => Response::with_payload(
+                format!("state of operator {}", self.id),
+                Payload::OperatorState {
+                    state: op.state().clone(),
+                },
+            ),
+            None => Error::OperatorNotFound(format!("operator {} is not loaded", self.id)).into(),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{
+    ipc::{
+        Payload,
+        command_context::CommandContext,
+        commands::{ExecCommand, Response},
+    },
+    texture::texture_cache,
+};
+use serde::{Deserialize, Serialize};
+
+/// Reports how many textures the host's [`TextureCache`](crate::texture::TextureCache) holds
+/// and how well it is hit.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetTextureStatsCommand {}
+
+impl ExecCommand for GetTextureStatsCommand {
+    fn execute(&self, _ctx: &mut CommandContext) -> Response {
+        let stats = texture_cache().stats();
+        Response::with_payload(
+            format!(
+                "{} host textures cached ({} bytes, {} references)",
+                stats.textures, stats.bytes, stats.references
+            ),
+            Payload::TextureStats { stats },
+        )
+    }
+}
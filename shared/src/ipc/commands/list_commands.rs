@@ -0,0 +1,37 @@
+use crate::{
+    command_provider::CommandSpec,
+    ipc::{
+        CommandInfo, Payload,
+        command_context::CommandContext,
+        commands::{Command, ExecCommand, Response},
+    },
+    plugin::{Plugin, types::command_plugin::CommandPlugin},
+};
+use serde::{Deserialize, Serialize};
+
+/// Lists the built-in commands and the commands registered by plugins, with their schemas.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListCommandsCommand {}
+
+impl ExecCommand for ListCommandsCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let mut commands: Vec<CommandInfo> = Command::NAMES
+            .iter()
+            .map(|name| CommandInfo {
+                plugin: None,
+                spec: CommandSpec::new(*name, serde_json::Value::Null),
+            })
+            .collect();
+        let registry = ctx.plugin_registry().read().unwrap();
+        for plugin in registry.plugins_of::<CommandPlugin>() {
+            commands.extend(plugin.commands().iter().map(|spec| CommandInfo {
+                plugin: Some(plugin.name().to_owned()),
+                spec: spec.clone(),
+            }));
+        }
+        Response::with_payload(
+            format!("{} commands available", commands.len()),
+            Payload::Commands { commands },
+        )
+    }
+}
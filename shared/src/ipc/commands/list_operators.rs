@@ -0,0 +1,26 @@
+use crate::ipc::{
+    Payload,
+    command_context::CommandContext,
+    commands::{ExecCommand, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListOperatorsCommand {}
+
+impl ExecCommand for ListOperatorsCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let mut operators: Vec<_> = ctx
+            .operators()
+            .read()
+            .unwrap()
+            .values()
+            .map(|op| op.state().clone())
+            .collect();
+        operators.sort_by(|a, b| a.id.cmp(&b.id));
+        Response::with_payload(
+            format!("{} operators spawned", operators.len()),
+            Payload::Operators { operators },
+        )
+    }
+}
@@ -0,0 +1,19 @@
+use crate::ipc::{
+    Payload,
+    command_context::CommandContext,
+    commands::{ExecCommand, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListPluginsCommand {}
+
+impl ExecCommand for ListPluginsCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let plugins = ctx.plugin_registry().read().unwrap().plugin_infos();
+        Response::with_payload(
+            format!("{} plugins loaded", plugins.len()),
+            Payload::Plugins { plugins },
+        )
+    }
+}
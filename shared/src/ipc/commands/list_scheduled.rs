@@ -0,0 +1,19 @@
+use crate::ipc::{
+    Payload,
+    command_context::CommandContext,
+    commands::{ExecCommand, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListScheduledCommand {}
+
+impl ExecCommand for ListScheduledCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let scheduled = ctx.scheduler().lock().unwrap().list();
+        Response::with_payload(
+            format!("{} events scheduled", scheduled.len()),
+            Payload::ScheduledEvents { scheduled },
+        )
+    }
+}
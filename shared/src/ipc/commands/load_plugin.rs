@@ -1,34 +1,58 @@
 use crate::{
     ipc::{
+        ErrorCode, Payload,
         command_context::CommandContext,
         commands::{ExecCommand, Response},
+        notification::Notification,
     },
-    plugin::types::operator_plugin::OperatorPlugin,
+    plugin::types,
 };
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tracing::debug;
 
+/// Loads the plugin at `path`. Fails if a plugin of the same name is registered; use
+/// `ReloadPlugin` to replace it, or `UnloadPlugin` it first.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoadPluginCommand {
-    name: String,
+    /// Defaults to the id in the plugin's manifest.
+    #[serde(default)]
+    name: Option<String>,
     path: PathBuf,
 }
 
+impl LoadPluginCommand {
+    pub(crate) fn new(name: Option<String>, path: PathBuf) -> Self {
+        Self { name, path }
+    }
+}
+
 impl ExecCommand for LoadPluginCommand {
     fn execute(&self, ctx: &mut CommandContext) -> Response {
-        match OperatorPlugin::new(self.path.as_path(), self.name.to_owned()) {
-            Ok(plugin) => {
-                ctx.plugin_registry()
-                    .write()
-                    .unwrap()
-                    .register_plugin(self.name.clone(), Box::new(plugin));
-                debug!("Loaded plugin: {}", self.name);
-                Response::Success(format!("Loaded plugin: {}", self.name))
+        let (path, copy) = {
+            let registry = ctx.plugin_registry().read().unwrap();
+            (registry.resolve_path(&self.path), registry.copy_libraries())
+        };
+        let loaded = types::load(path.as_path(), self.name.clone(), copy).and_then(|plugin| {
+            let name = plugin.name().to_owned();
+            let mut registry = ctx.plugin_registry().write().unwrap();
+            registry.check_unregistered(&name)?;
+            registry.check_commands(&name, plugin.as_ref())?;
+            registry.register_plugin(name.clone(), plugin)?;
+            Ok(name)
+        });
+        match loaded {
+            Ok(name) => {
+                debug!("Loaded plugin: {}", name);
+                ctx.notify(Notification::PluginLoaded { name: name.clone() });
+                Response::with_payload(
+                    format!("Loaded plugin: {}", name),
+                    Payload::PluginLoaded { name, path },
+                )
             }
             Err(e) => {
                 debug!("Failed to load plugin: {:?}", e);
-                Response::Error(format!("Failed to load plugin: {:?}", e))
+                Response::error(ErrorCode::from(&e), format!("Failed to load plugin: {}", e))
             }
         }
     }
@@ -0,0 +1,129 @@
+use crate::{
+    events::Event,
+    ipc::{
+        ErrorCode, Payload,
+        command_context::CommandContext,
+        commands::{
+            ExecCommand, Response, load_plugin::LoadPluginCommand,
+            spawn_operator::SpawnOperatorCommand,
+        },
+        notification::Notification,
+    },
+    session::{self, DEFAULT_SESSION, OperatorEntry, Session},
+    transform::TransformPatch,
+};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Replaces all spawned operators with the ones recorded in a session profile.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoadSessionCommand {
+    #[serde(default = "default_session")]
+    name: String,
+}
+
+fn default_session() -> String {
+    DEFAULT_SESSION.to_owned()
+}
+
+impl ExecCommand for LoadSessionCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let session = match Session::load(&self.name) {
+            Ok(session) => session,
+            Err(e) => return session_error(&self.name, e),
+        };
+
+        let retreated: Vec<String> = ctx
+            .operators()
+            .write()
+            .unwrap()
+            .drain()
+            .map(|(id, _)| id)
+            .collect();
+        for id in retreated {
+            ctx.notify(Notification::OperatorRetreated { id });
+        }
+
+        let errors = restore_session(&session, ctx);
+        Response::with_payload(
+            format!("Loaded session {} ({} failures)", self.name, errors.len()),
+            Payload::SessionLoaded {
+                name: self.name.clone(),
+                errors,
+            },
+        )
+    }
+}
+
+pub(crate) fn session_error(name: &str, e: session::Error) -> Response {
+    debug!("Session {} failed: {}", name, e);
+    Response::error(
+        ErrorCode::SessionError,
+        format!("Session {} failed: {}", name, e),
+    )
+}
+
+pub(crate) fn restore_session(session: &Session, ctx: &mut CommandContext) -> Vec<String> {
+    let mut errors = Vec::new();
+    for plugin in &session.plugins {
+        if ctx
+            .plugin_registry()
+            .read()
+            .unwrap()
+            .get_plugin(&plugin.name)
+            .is_ok()
+        {
+            continue;
+        }
+        let response =
+            LoadPluginCommand::new(Some(plugin.name.clone()), plugin.path.clone()).execute(ctx);
+        if !response.is_success() {
+            errors.push(response.to_string());
+        }
+    }
+
+    for entry in &session.operators {
+        match restore_operator(entry, ctx) {
+            Ok(problems) => errors.extend(problems),
+            Err(e) => errors.push(e),
+        }
+    }
+    errors
+}
+
+/// Spawns the operator recorded in `entry` and puts back its skin and animation. Fails only if
+/// the operator could not be spawned; a skin it rejects is returned as a problem, and the
+/// animation is restored regardless.
+pub(crate) fn restore_operator(
+    entry: &OperatorEntry,
+    ctx: &mut CommandContext,
+) -> Result<Vec<String>, String> {
+    let response = SpawnOperatorCommand::new(
+        entry.plugin.clone(),
+        Some(entry.id.clone()),
+        TransformPatch::from(entry.transform),
+        entry.behaviour.clone(),
+    )
+    .execute(ctx);
+    if !response.is_success() {
+        return Err(response.to_string());
+    }
+    let mut problems = Vec::new();
+    if let Some(op) = ctx.operators().write().unwrap().get_mut(&entry.id) {
+        if let Some(skin) = &entry.skin
+            && let Err(e) = op.event_handler(Event::SetSkin {
+                op_id: entry.id.clone(),
+                skin: skin.clone(),
+            })
+        {
+            problems.push(format!(
+                "Operator {} rejected skin {}: {}",
+                entry.id, skin, e
+            ));
+        }
+        if let Some(animation) = &entry.animation {
+            op.start_animation(animation);
+        }
+    }
+    Ok(problems)
+}
@@ -2,18 +2,46 @@ use crate::ipc::{
     Response,
     command_context::CommandContext,
     commands::{
-        load_plugin::LoadPluginCommand, retreat_operator::RetreatOperatorCommand,
-        schedule_event::ScheduleEventCommand, spawn_operator::SpawnOperatorCommand,
-        unload_plugin::UnloadPluginCommand,
+        cancel_scheduled::CancelScheduledCommand, get_operator_state::GetOperatorStateCommand,
+        get_texture_stats::GetTextureStatsCommand, list_commands::ListCommandsCommand,
+        list_operators::ListOperatorsCommand, list_plugins::ListPluginsCommand,
+        list_scheduled::ListScheduledCommand, load_plugin::LoadPluginCommand,
+        load_session::LoadSessionCommand, plugin_command::PluginCommandCommand,
+        reload_plugin::ReloadPluginCommand, rescan_plugins::RescanPluginsCommand,
+        retreat_operator::RetreatOperatorCommand, save_session::SaveSessionCommand,
+        schedule_event::ScheduleEventCommand, set_behaviour::SetBehaviourCommand,
+        set_transform::SetTransformCommand, spawn_operator::SpawnOperatorCommand,
+        subscribe::SubscribeCommand, unload_plugin::UnloadPluginCommand,
+        unsubscribe::UnsubscribeCommand,
     },
 };
+pub(crate) use load_session::restore_session;
+pub(crate) use reload_plugin::reload_plugin;
+pub(crate) use rescan_plugins::rescan_plugins;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt::Debug;
+mod cancel_scheduled;
+mod get_operator_state;
+mod get_texture_stats;
+mod list_commands;
+mod list_operators;
+mod list_plugins;
+mod list_scheduled;
 mod load_plugin;
+mod load_session;
+mod plugin_command;
+mod reload_plugin;
+mod rescan_plugins;
 mod retreat_operator;
+mod save_session;
 mod schedule_event;
+mod set_behaviour;
+mod set_transform;
 mod spawn_operator;
+mod subscribe;
 mod unload_plugin;
+mod unsubscribe;
 
 pub trait ExecCommand: std::fmt::Debug + Send + Sync {
     fn execute(&self, ctx: &mut CommandContext) -> Response;
@@ -28,8 +56,37 @@ pub enum Error {
     CommandDoesNotExist(String),
     MutexPoisoned(String),
     ContextInvalid(String),
+    OperatorNotFound(String),
+    OperatorAlreadyExists(String),
+    InvalidSchedule(String),
+    ScheduleNotFound(String),
+    CommandFailed(String),
+    EventRejected(String),
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::SerdeError(e) => write!(f, "Invalid command: {}", e),
+            Error::CommandRegistrationFailed(e) => write!(f, "Command registration failed: {}", e),
+            Error::CommandDeregistrationFailed(e) => {
+                write!(f, "Command deregistration failed: {}", e)
+            }
+            Error::CommandDoesNotExist(e) => write!(f, "Command does not exist: {}", e),
+            Error::MutexPoisoned(e) => write!(f, "Mutex poisoned: {}", e),
+            Error::ContextInvalid(e) => write!(f, "Command context invalid: {}", e),
+            Error::OperatorNotFound(e) => write!(f, "Operator not found: {}", e),
+            Error::OperatorAlreadyExists(e) => write!(f, "Operator already exists: {}", e),
+            Error::InvalidSchedule(e) => write!(f, "Invalid schedule: {}", e),
+            Error::ScheduleNotFound(e) => write!(f, "Scheduled event not found: {}", e),
+            Error::CommandFailed(e) => write!(f, "Command failed: {}", e),
+            Error::EventRejected(e) => write!(f, "Event rejected: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl From<serde_json::Error> for Error {
     fn from(value: serde_json::Error) -> Self {
         Self::SerdeError(value.to_string())
@@ -45,9 +102,50 @@ pub enum Command {
     SpawnOperator(SpawnOperatorCommand),
     ScheduleEvent(ScheduleEventCommand),
     RetreatOperator(RetreatOperatorCommand),
+    Subscribe(SubscribeCommand),
+    Unsubscribe(UnsubscribeCommand),
+    ListPlugins(ListPluginsCommand),
+    ListOperators(ListOperatorsCommand),
+    GetOperatorState(GetOperatorStateCommand),
+    SetTransform(SetTransformCommand),
+    SetBehaviour(SetBehaviourCommand),
+    ListScheduled(ListScheduledCommand),
+    CancelScheduled(CancelScheduledCommand),
+    SaveSession(SaveSessionCommand),
+    LoadSession(LoadSessionCommand),
+    RescanPlugins(RescanPluginsCommand),
+    ReloadPlugin(ReloadPluginCommand),
+    PluginCommand(PluginCommandCommand),
+    ListCommands(ListCommandsCommand),
+    GetTextureStats(GetTextureStatsCommand),
 }
 
 impl Command {
+    /// The `command` tags of the built-in commands.
+    pub const NAMES: &[&str] = &[
+        "LoadPlugin",
+        "UnloadPlugin",
+        "SpawnOperator",
+        "ScheduleEvent",
+        "RetreatOperator",
+        "Subscribe",
+        "Unsubscribe",
+        "ListPlugins",
+        "ListOperators",
+        "GetOperatorState",
+        "SetTransform",
+        "SetBehaviour",
+        "ListScheduled",
+        "CancelScheduled",
+        "SaveSession",
+        "LoadSession",
+        "RescanPlugins",
+        "ReloadPlugin",
+        "PluginCommand",
+        "ListCommands",
+        "GetTextureStats",
+    ];
+
     pub fn execute(self, ctx: &mut CommandContext) -> Response {
         match self {
             Command::LoadPlugin(cmd) => cmd.execute(ctx),
@@ -55,6 +153,22 @@ impl Command {
             Command::SpawnOperator(cmd) => cmd.execute(ctx),
             Command::ScheduleEvent(cmd) => cmd.execute(ctx),
             Command::RetreatOperator(cmd) => cmd.execute(ctx),
+            Command::Subscribe(cmd) => cmd.execute(ctx),
+            Command::Unsubscribe(cmd) => cmd.execute(ctx),
+            Command::ListPlugins(cmd) => cmd.execute(ctx),
+            Command::ListOperators(cmd) => cmd.execute(ctx),
+            Command::GetOperatorState(cmd) => cmd.execute(ctx),
+            Command::SetTransform(cmd) => cmd.execute(ctx),
+            Command::SetBehaviour(cmd) => cmd.execute(ctx),
+            Command::ListScheduled(cmd) => cmd.execute(ctx),
+            Command::CancelScheduled(cmd) => cmd.execute(ctx),
+            Command::SaveSession(cmd) => cmd.execute(ctx),
+            Command::LoadSession(cmd) => cmd.execute(ctx),
+            Command::RescanPlugins(cmd) => cmd.execute(ctx),
+            Command::ReloadPlugin(cmd) => cmd.execute(ctx),
+            Command::PluginCommand(cmd) => cmd.execute(ctx),
+            Command::ListCommands(cmd) => cmd.execute(ctx),
+            Command::GetTextureStats(cmd) => cmd.execute(ctx),
         }
     }
 
@@ -63,7 +177,23 @@ impl Command {
     }
 
     pub fn execute_from_json(value: &str, ctx: &mut CommandContext) -> Result<Response, Error> {
-        let command = Command::from_json(value)?;
+        Self::execute_value(serde_json::from_str(value)?, ctx)
+    }
+
+    /// Executes a command given as a JSON object. A `command` tag that is not a built-in
+    /// command is dispatched to the plugin that registered it, with the rest of the object as
+    /// its arguments.
+    pub fn execute_value(mut value: Value, ctx: &mut CommandContext) -> Result<Response, Error> {
+        if let Some(tag) = value.get("command").and_then(Value::as_str)
+            && !Self::NAMES.contains(&tag)
+        {
+            let name = tag.to_owned();
+            if let Some(args) = value.as_object_mut() {
+                args.remove("command");
+            }
+            return Ok(plugin_command::run_plugin_command(ctx, None, &name, value));
+        }
+        let command: Command = serde_json::from_value(value)?;
         Ok(command.execute(ctx))
     }
 }
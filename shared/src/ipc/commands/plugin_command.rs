@@ -0,0 +1,72 @@
+use crate::{
+    ipc::{
+        ErrorCode, Payload,
+        command_context::CommandContext,
+        commands::{Error, ExecCommand, Response},
+    },
+    plugin::{cast_plugin_to, types::command_plugin::CommandPlugin},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+/// Runs a command added by a command plugin, naming the plugin explicitly. Plugin commands
+/// can also be sent like built-in commands, see [`super::Command::execute_value`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PluginCommandCommand {
+    plugin: String,
+    name: String,
+    #[serde(default)]
+    args: Value,
+}
+
+impl ExecCommand for PluginCommandCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        run_plugin_command(ctx, Some(&self.plugin), &self.name, self.args.clone())
+    }
+}
+
+/// Executes plugin command `name` with `args`, looked up in `plugin` or, if `None`, in
+/// whichever plugin registered it.
+pub(crate) fn run_plugin_command(
+    ctx: &mut CommandContext,
+    plugin: Option<&str>,
+    name: &str,
+    args: Value,
+) -> Response {
+    let registry = ctx.plugin_registry().read().unwrap();
+    let plugin = match plugin {
+        Some(plugin) => match registry
+            .get_plugin(plugin)
+            .and_then(cast_plugin_to::<CommandPlugin>)
+        {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                debug!("Failed to get command plugin {}: {:?}", plugin, e);
+                return Response::error(
+                    ErrorCode::from(&e),
+                    format!("Failed to get command plugin {}: {}", plugin, e),
+                );
+            }
+        },
+        None => match registry.command_owner(name) {
+            Some(plugin) => plugin,
+            None => return Error::CommandDoesNotExist(format!("Unknown command {}", name)).into(),
+        },
+    };
+    let plugin_name = crate::plugin::Plugin::name(plugin);
+    match plugin.execute(name, args) {
+        Ok(result) => Response::with_payload(
+            format!("Executed {} from plugin {}", name, plugin_name),
+            Payload::PluginCommandResult {
+                plugin: plugin_name.to_owned(),
+                name: name.to_owned(),
+                result,
+            },
+        ),
+        Err(e) => {
+            debug!("Plugin command {}.{} failed: {}", plugin_name, name, e);
+            e.into()
+        }
+    }
+}
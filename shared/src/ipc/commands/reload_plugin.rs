@@ -0,0 +1,93 @@
+use crate::{
+    ipc::{
+        ErrorCode, Payload,
+        command_context::CommandContext,
+        commands::{ExecCommand, Response, load_session::restore_operator},
+        notification::Notification,
+    },
+    operator::instances_of,
+    plugin::{self, PluginReload},
+    session::OperatorEntry,
+};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Reloads a plugin from its library file, respawning its operators in place.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReloadPluginCommand {
+    name: String,
+}
+
+impl ExecCommand for ReloadPluginCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        match reload_plugin(ctx, &self.name) {
+            Ok(reload) => Response::with_payload(
+                format!(
+                    "Reloaded plugin {} ({} operators respawned, {} failures)",
+                    self.name,
+                    reload.respawned.len(),
+                    reload.errors.len()
+                ),
+                Payload::PluginReloaded {
+                    name: self.name.clone(),
+                    reload,
+                },
+            ),
+            Err(e) => {
+                debug!("Failed to reload plugin {}: {:?}", self.name, e);
+                Response::error(
+                    ErrorCode::from(&e),
+                    format!("Failed to reload plugin {}: {}", self.name, e),
+                )
+            }
+        }
+    }
+}
+
+pub(crate) fn reload_plugin(
+    ctx: &mut CommandContext,
+    name: &str,
+) -> Result<PluginReload, plugin::Error> {
+    let plugin = {
+        let registry = ctx.plugin_registry().read().unwrap();
+        let plugin = registry.get_plugin(name)?.reload()?;
+        registry.check_commands(name, plugin.as_ref())?;
+        plugin
+    };
+
+    // Instances keep the old library loaded, so they go before it is replaced.
+    let entries: Vec<OperatorEntry> = {
+        let mut operators = ctx.operators().write().unwrap();
+        instances_of(&operators, name)
+            .iter()
+            .filter_map(|id| operators.remove(id))
+            .map(|op| OperatorEntry::from(&op))
+            .collect()
+    };
+    for entry in &entries {
+        ctx.notify(Notification::OperatorRetreated {
+            id: entry.id.clone(),
+        });
+    }
+
+    ctx.plugin_registry()
+        .write()
+        .unwrap()
+        .replace_plugin(name.to_owned(), plugin);
+    debug!("Reloaded plugin: {}", name);
+    ctx.notify(Notification::PluginLoaded {
+        name: name.to_owned(),
+    });
+
+    let mut reload = PluginReload::default();
+    for entry in entries {
+        match restore_operator(&entry, ctx) {
+            Ok(problems) => {
+                reload.respawned.push(entry.id);
+                reload.errors.extend(problems);
+            }
+            Err(e) => reload.errors.push(e),
+        }
+    }
+    Ok(reload)
+}
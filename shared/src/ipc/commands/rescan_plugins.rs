@@ -0,0 +1,98 @@
+use crate::{
+    ipc::{
+        Payload,
+        command_context::CommandContext,
+        commands::{ExecCommand, Response, load_plugin::LoadPluginCommand},
+    },
+    plugin::{PluginScan, types},
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::debug;
+
+/// Scans the plugin search paths and loads every plugin not registered yet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RescanPluginsCommand {}
+
+impl ExecCommand for RescanPluginsCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let scan = rescan_plugins(ctx);
+        Response::with_payload(
+            format!(
+                "{} plugins added, {} changed, {} missing ({} failures)",
+                scan.added.len(),
+                scan.changed.len(),
+                scan.missing.len(),
+                scan.errors.len()
+            ),
+            Payload::PluginsRescanned { scan },
+        )
+    }
+}
+
+pub(crate) fn rescan_plugins(ctx: &mut CommandContext) -> PluginScan {
+    let mut scan = PluginScan::default();
+    let (found, registered) = {
+        let registry = ctx.plugin_registry().read().unwrap();
+        let registered: Vec<_> = registry
+            .plugin_list()
+            .into_iter()
+            .filter_map(|name| {
+                let plugin = registry.get_plugin(&name).ok()?;
+                Some((name, plugin.path().to_path_buf(), plugin.modified()))
+            })
+            .collect();
+        (registry.discover(), registered)
+    };
+
+    for (name, path, modified) in &registered {
+        if !path.exists() {
+            scan.missing.push(name.clone());
+        } else if modified.is_some() && *modified != types::modified(path) {
+            scan.changed.push(name.clone());
+        }
+    }
+
+    for path in found {
+        if registered
+            .iter()
+            .any(|(_, registered, _)| same_file(registered, &path))
+        {
+            continue;
+        }
+        let manifest = match types::read_manifest(&path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                scan.errors.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+        };
+        if registered.iter().any(|(name, _, _)| *name == manifest.id) {
+            debug!(
+                "Skipping {}, plugin {} is already registered",
+                path.display(),
+                manifest.id
+            );
+            scan.errors.push(format!(
+                "{}: plugin {} is already registered from another path",
+                path.display(),
+                manifest.id
+            ));
+            continue;
+        }
+        let response = LoadPluginCommand::new(None, path).execute(ctx);
+        if response.is_success() {
+            scan.added.push(manifest.id);
+        } else {
+            scan.errors.push(response.to_string());
+        }
+    }
+    scan
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
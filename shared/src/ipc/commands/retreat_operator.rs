@@ -1,21 +1,31 @@
-use crate::ipc::commands::{ExecCommand, Response};
+use crate::ipc::{
+    Payload,
+    commands::{Error, ExecCommand, Response},
+    notification::Notification,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RetreatOperatorCommand {
-    name: String,
-    position: (i32, i32),
+    /// Instance id of the operator.
+    #[serde(alias = "name")]
+    id: String,
 }
 
 impl ExecCommand for RetreatOperatorCommand {
     fn execute(&self, ctx: &mut crate::ipc::command_context::CommandContext) -> Response {
-        if let Some(_) = ctx.operators().write().unwrap().remove(&self.name) {
-            Response::Success(format!(
-                "retreated operator {} at {:?}",
-                self.name, self.position
-            ))
+        if ctx.operators().write().unwrap().remove(&self.id).is_some() {
+            ctx.notify(Notification::OperatorRetreated {
+                id: self.id.clone(),
+            });
+            Response::with_payload(
+                format!("retreated operator {}", self.id),
+                Payload::OperatorRetreated {
+                    id: self.id.clone(),
+                },
+            )
         } else {
-            Response::Error(format!("operator {} is not loaded", self.name))
+            Error::OperatorNotFound(format!("operator {} is not loaded", self.id)).into()
         }
     }
 }
@@ -0,0 +1,37 @@
+use crate::{
+    ipc::{
+        Payload,
+        command_context::CommandContext,
+        commands::{ExecCommand, Response, load_session::session_error},
+    },
+    session::{DEFAULT_SESSION, Session},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveSessionCommand {
+    #[serde(default = "default_session")]
+    name: String,
+}
+
+fn default_session() -> String {
+    DEFAULT_SESSION.to_owned()
+}
+
+impl ExecCommand for SaveSessionCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let plugins = ctx.plugin_registry().clone();
+        let operators = ctx.operators().clone();
+        let session = Session::capture(&plugins.read().unwrap(), &operators.read().unwrap());
+        match session.save(&self.name) {
+            Ok(path) => Response::with_payload(
+                format!("Saved session {} to {}", self.name, path.display()),
+                Payload::SessionSaved {
+                    name: self.name.clone(),
+                    path,
+                },
+            ),
+            Err(e) => session_error(&self.name, e),
+        }
+    }
+}
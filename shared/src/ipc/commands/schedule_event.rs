@@ -1,23 +1,130 @@
 use crate::{
-    events::Event,
+    events::{Delivery, Event, PendingEvent},
     ipc::{
+        Payload,
         command_context::CommandContext,
-        commands::{ExecCommand, Response},
+        commands::{Error, ExecCommand, Response},
     },
+    scheduler::Schedule,
 };
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
+
+/// How long a `wait`ing ScheduleEvent blocks for the delivery report. This blocks the calling
+/// thread, so async callers run commands on the blocking pool.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScheduleEventCommand {
     event: Event,
+    /// Sends the event right away if omitted.
+    #[serde(default)]
+    schedule: Option<Schedule>,
+    /// Waits until the operator has handled the event and reports the outcome, instead of
+    /// answering as soon as the event is queued. Only for events sent right away.
+    #[serde(default)]
+    wait: bool,
+}
+
+impl ScheduleEventCommand {
+    /// Rejects custom events the target operator does not accept. An event sent right away
+    /// must also target an existing operator; a scheduled one may target an operator that is
+    /// spawned later.
+    fn check(&self, ctx: &mut CommandContext) -> Result<(), Error> {
+        if self.wait && self.schedule.is_some() {
+            return Err(Error::InvalidSchedule(
+                "wait cannot be combined with a schedule".to_owned(),
+            ));
+        }
+        let plugins = ctx.plugin_registry().clone();
+        let plugins = plugins.read().unwrap();
+        let operators = ctx.operators().read().unwrap();
+        let op_id = self.event.operator_id();
+        if self.schedule.is_none() && !operators.contains_key(op_id) {
+            return Err(Error::OperatorNotFound(format!(
+                "Operator {} is not spawned",
+                op_id
+            )));
+        }
+        self.event
+            .check_accepted(&plugins, &operators)
+            .map_err(|e| {
+                debug!("Rejected event {:?}: {}", self.event, e);
+                Error::EventRejected(e)
+            })
+    }
 }
 
 impl ExecCommand for ScheduleEventCommand {
     fn execute(&self, ctx: &mut CommandContext) -> Response {
-        ctx.sender().send(self.event.clone()).unwrap();
-        Response::Success(format!(
-            "Scheduled event {}",
-            serde_json::to_string(&self.event).unwrap()
-        ))
+        if let Err(e) = self.check(ctx) {
+            return e.into();
+        }
+        if let Some(schedule) = &self.schedule {
+            let mut scheduler = ctx.scheduler().lock().unwrap();
+            return match scheduler.schedule(
+                self.event.clone(),
+                schedule.clone(),
+                chrono::Local::now(),
+            ) {
+                Ok(scheduled) => Response::with_payload(
+                    format!(
+                        "Scheduled event {} as {} for {}",
+                        serde_json::to_string(&self.event).unwrap(),
+                        scheduled.id,
+                        scheduled.next_fire
+                    ),
+                    Payload::Scheduled {
+                        scheduled: scheduled.clone(),
+                    },
+                ),
+                Err(e) => Error::InvalidSchedule(e).into(),
+            };
+        }
+
+        let (reply, delivery) = std::sync::mpsc::channel();
+        let pending = if self.wait {
+            PendingEvent::with_reply(self.event.clone(), reply)
+        } else {
+            PendingEvent::new(self.event.clone())
+        };
+        if let Err(e) = ctx.sender().send(pending) {
+            return Error::ContextInvalid(format!("event channel closed: {}", e)).into();
+        }
+        if !self.wait {
+            return Response::with_payload(
+                format!(
+                    "Queued event {}",
+                    serde_json::to_string(&self.event).unwrap()
+                ),
+                Payload::EventScheduled {
+                    event: self.event.clone(),
+                    delivery: None,
+                },
+            );
+        }
+
+        match delivery.recv_timeout(DELIVERY_TIMEOUT) {
+            Ok(Delivery::Delivered) => Response::with_payload(
+                format!(
+                    "Delivered event {}",
+                    serde_json::to_string(&self.event).unwrap()
+                ),
+                Payload::EventScheduled {
+                    event: self.event.clone(),
+                    delivery: Some(Delivery::Delivered),
+                },
+            ),
+            Ok(Delivery::OperatorNotFound) => {
+                Error::OperatorNotFound(format!("Operator {} is gone", self.event.operator_id()))
+                    .into()
+            }
+            Ok(Delivery::Rejected { reason }) => {
+                debug!("Operator rejected event {:?}: {}", self.event, reason);
+                Error::EventRejected(format!("rejected by operator: {}", reason)).into()
+            }
+            Err(e) => Error::ContextInvalid(format!("no delivery report: {}", e)).into(),
+        }
     }
 }
@@ -0,0 +1,46 @@
+use crate::{
+    behaviour::BehaviourConfig,
+    ipc::{
+        Payload,
+        command_context::CommandContext,
+        commands::{Error, ExecCommand, Response},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// Enables the autonomous behaviour of an operator, or disables it if `config` is omitted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetBehaviourCommand {
+    id: String,
+    #[serde(default)]
+    config: Option<BehaviourConfig>,
+}
+
+impl ExecCommand for SetBehaviourCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        if let Some(Err(e)) = self.config.as_ref().map(BehaviourConfig::validate) {
+            return Error::SerdeError(e).into();
+        }
+        match ctx.operators().write().unwrap().get_mut(&self.id) {
+            Some(op) => {
+                op.set_behaviour(self.config.clone());
+                Response::with_payload(
+                    format!(
+                        "{} behaviour of operator {}",
+                        if self.config.is_some() {
+                            "enabled"
+                        } else {
+                            "disabled"
+                        },
+                        self.id
+                    ),
+                    Payload::Behaviour {
+                        id: self.id.clone(),
+                        config: self.config.clone(),
+                    },
+                )
+            }
+            None => Error::OperatorNotFound(format!("operator {} is not loaded", self.id)).into(),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{
+    ipc::{
+        Payload,
+        command_context::CommandContext,
+        commands::{Error, ExecCommand, Response},
+    },
+    transform::TransformPatch,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetTransformCommand {
+    id: String,
+    #[serde(flatten)]
+    transform: TransformPatch,
+}
+
+impl ExecCommand for SetTransformCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        match ctx.operators().write().unwrap().get_mut(&self.id) {
+            Some(op) => {
+                op.transform_mut().patch(&self.transform);
+                Response::with_payload(
+                    format!("updated transform of operator {}", self.id),
+                    Payload::Transform {
+                        id: self.id.clone(),
+                        transform: *op.transform(),
+                    },
+                )
+            }
+            None => Error::OperatorNotFound(format!("operator {} is not loaded", self.id)).into(),
+        }
+    }
+}
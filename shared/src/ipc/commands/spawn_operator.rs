@@ -1,39 +1,126 @@
 use crate::{
-    ipc::commands::{ExecCommand, Response},
-    plugin::types::operator_plugin::OperatorPlugin,
+    behaviour::BehaviourConfig,
+    ipc::{
+        ErrorCode, Payload,
+        command_context::CommandContext,
+        commands::{Error, ExecCommand, Response},
+        notification::Notification,
+    },
+    movement::IDLE_ANIMATION,
+    operator::{OperatorInstance, OperatorRegistry},
+    plugin::types,
+    transform::{Transform, TransformPatch},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SpawnOperatorCommand {
+    /// Name of the operator or Spine plugin to instantiate.
     name: String,
-    position: (i32, i32),
+    /// Instance id; generated from the plugin name if omitted.
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(flatten)]
+    transform: TransformPatch,
+    /// Autonomous behaviour to enable right away.
+    #[serde(default)]
+    behaviour: Option<BehaviourConfig>,
+}
+
+impl SpawnOperatorCommand {
+    pub(crate) fn new(
+        name: String,
+        id: Option<String>,
+        transform: TransformPatch,
+        behaviour: Option<BehaviourConfig>,
+    ) -> Self {
+        Self {
+            name,
+            id,
+            transform,
+            behaviour,
+        }
+    }
 }
 
 impl ExecCommand for SpawnOperatorCommand {
-    fn execute(&self, ctx: &mut crate::ipc::command_context::CommandContext) -> Response {
-        let build_result = {
-            let binding = ctx.plugin_registry().read().unwrap();
-            let plugin = crate::plugin::cast_plugin_to::<OperatorPlugin>(
-                binding.get_plugin(&self.name).unwrap(),
-            )
-            .unwrap();
-            plugin.build(Some(self.name.clone()))
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        if let Some(Err(e)) = self.behaviour.as_ref().map(BehaviourConfig::validate) {
+            return Error::SerdeError(e).into();
+        }
+        let mut transform = Transform::default();
+        transform.patch(&self.transform);
+        let (plugins, operators) = (ctx.plugin_registry().clone(), ctx.operators().clone());
+        let spawned = {
+            // Same lock order as `Session::capture`. The id is picked and taken under one
+            // write lock, so concurrent spawns of the same plugin cannot pick the same one.
+            let plugins = plugins.read().unwrap();
+            let mut operators = operators.write().unwrap();
+            let id = match &self.id {
+                Some(id) => id.clone(),
+                None => next_instance_id(&operators, &self.name),
+            };
+            match operators.entry(id) {
+                Entry::Occupied(entry) => return already_spawned(entry.key()),
+                Entry::Vacant(entry) => plugins
+                    .get_plugin(&self.name)
+                    .and_then(|plugin| {
+                        let operator = types::build_operator(plugin, Some(entry.key().clone()))?;
+                        Ok((operator, plugin.manifest().animations.clone()))
+                    })
+                    .map(|(operator, animations)| {
+                        let mut instance = OperatorInstance::new(
+                            entry.key().clone(),
+                            self.name.clone(),
+                            operator,
+                            transform,
+                            animations,
+                        );
+                        instance.start_host_animation(IDLE_ANIMATION);
+                        instance.set_behaviour(self.behaviour.clone());
+                        entry.insert(instance).state().id.clone()
+                    }),
+            }
         };
 
-        match build_result {
-            Ok(mut v) => {
-                v.start_animation("Relax");
-                ctx.operators()
-                    .write()
-                    .unwrap()
-                    .insert(self.name.clone(), v);
-                Response::Success(format!(
-                    "spawned operator {} at {:?}",
-                    self.name, self.position
-                ))
+        match spawned {
+            Ok(id) => {
+                ctx.notify(Notification::OperatorSpawned {
+                    id: id.clone(),
+                    plugin: self.name.clone(),
+                });
+                Response::with_payload(
+                    format!(
+                        "spawned operator {} ({}) at {:?}",
+                        id, self.name, transform.position
+                    ),
+                    Payload::OperatorSpawned {
+                        id,
+                        plugin: self.name.clone(),
+                        transform,
+                    },
+                )
             }
-            Err(_) => todo!(),
+            Err(e) => Response::error(
+                ErrorCode::from(&e),
+                format!("Failed to spawn operator {}: {}", self.name, e),
+            ),
         }
     }
 }
+
+fn already_spawned(id: &str) -> Response {
+    Error::OperatorAlreadyExists(format!("operator {} is already spawned", id)).into()
+}
+
+/// The plugin name itself for the first instance, `name#2`, `name#3`, ... for further ones.
+fn next_instance_id(operators: &OperatorRegistry, plugin: &str) -> String {
+    if !operators.contains_key(plugin) {
+        return plugin.to_owned();
+    }
+    (2..)
+        .map(|n| format!("{}#{}", plugin, n))
+        .find(|id| !operators.contains_key(id))
+        .unwrap()
+}
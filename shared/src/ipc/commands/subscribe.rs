@@ -0,0 +1,27 @@
+use crate::ipc::{
+    Payload,
+    command_context::CommandContext,
+    commands::{ExecCommand, Response},
+    notification::SubscriptionFilter,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscribeCommand {
+    #[serde(flatten)]
+    filter: SubscriptionFilter,
+}
+
+impl ExecCommand for SubscribeCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let subscription_id = ctx
+            .subscriptions()
+            .lock()
+            .unwrap()
+            .subscribe(self.filter.clone());
+        Response::with_payload(
+            format!("Subscribed with id {}", subscription_id),
+            Payload::Subscribed { subscription_id },
+        )
+    }
+}
@@ -1,6 +1,12 @@
-use crate::ipc::{
-    command_context::CommandContext,
-    commands::{ExecCommand, Response},
+use crate::{
+    ipc::{
+        ErrorCode, Payload,
+        command_context::CommandContext,
+        commands::{ExecCommand, Response},
+        notification::Notification,
+    },
+    operator::instances_of,
+    plugin,
 };
 use serde::{Deserialize, Serialize};
 use tracing::debug;
@@ -8,27 +14,62 @@ use tracing::debug;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UnloadPluginCommand {
     name: String,
+    /// Retreat the plugin's operators instead of refusing to unload while any are spawned.
+    #[serde(default)]
+    force: bool,
 }
 
 impl ExecCommand for UnloadPluginCommand {
     fn execute(&self, ctx: &mut CommandContext) -> Response {
-        match ctx
-            .plugin_registry()
-            .write()
-            .unwrap()
-            .deregister_plugin(&self.name)
-        {
-            Ok(plugin) => {
-                debug!("Unloaded plugin: {} / {}", self.name, plugin.name());
-                Response::Success(format!(
-                    "Unloaded plugin: {} / {}",
+        let (plugins, operators) = (ctx.plugin_registry().clone(), ctx.operators().clone());
+        let result = {
+            // Same lock order as `Session::capture`.
+            let mut plugins = plugins.write().unwrap();
+            let mut operators = operators.write().unwrap();
+            let instances = instances_of(&operators, &self.name);
+            if !instances.is_empty() && !self.force {
+                Err(plugin::Error::PluginInUse(format!(
+                    "{} still has operators spawned: {}",
                     self.name,
-                    plugin.name()
-                ))
+                    instances.join(", ")
+                )))
+            } else {
+                plugins.deregister_plugin(&self.name).map(|plugin| {
+                    for id in &instances {
+                        operators.remove(id);
+                    }
+                    (plugin, instances)
+                })
+            }
+        };
+        match result {
+            Ok((plugin, retreated)) => {
+                debug!("Unloaded plugin: {} / {}", self.name, plugin.name());
+                for id in &retreated {
+                    ctx.notify(Notification::OperatorRetreated { id: id.clone() });
+                }
+                ctx.notify(Notification::PluginUnloaded {
+                    name: self.name.clone(),
+                });
+                Response::with_payload(
+                    format!(
+                        "Unloaded plugin: {} / {} ({} operators retreated)",
+                        self.name,
+                        plugin.name(),
+                        retreated.len()
+                    ),
+                    Payload::PluginUnloaded {
+                        name: self.name.clone(),
+                        retreated,
+                    },
+                )
             }
             Err(e) => {
                 debug!("Failed to unload plugin: {}", e);
-                Response::Error(format!("Failed to unload plugin: {}", e))
+                Response::error(
+                    ErrorCode::from(&e),
+                    format!("Failed to unload plugin: {}", e),
+                )
             }
         }
     }
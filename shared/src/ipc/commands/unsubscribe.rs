@@ -0,0 +1,33 @@
+use crate::ipc::{
+    ErrorCode, Payload,
+    command_context::CommandContext,
+    commands::{ExecCommand, Response},
+};
+use serde::{Deserialize, Serialize};
+
+/// Removes a single subscription, or all subscriptions of the connection if no id is given.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnsubscribeCommand {
+    #[serde(default)]
+    subscription_id: Option<u64>,
+}
+
+impl ExecCommand for UnsubscribeCommand {
+    fn execute(&self, ctx: &mut CommandContext) -> Response {
+        let mut subscriptions = ctx.subscriptions().lock().unwrap();
+        let subscription_ids = match self.subscription_id {
+            Some(id) if subscriptions.unsubscribe(id) => vec![id],
+            Some(id) => {
+                return Response::error(
+                    ErrorCode::SubscriptionNotFound,
+                    format!("subscription {} does not exist", id),
+                );
+            }
+            None => subscriptions.clear(),
+        };
+        Response::with_payload(
+            format!("Unsubscribed {:?}", subscription_ids),
+            Payload::Unsubscribed { subscription_ids },
+        )
+    }
+}
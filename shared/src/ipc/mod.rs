@@ -1,18 +1,331 @@
 pub mod command_context;
 pub mod commands;
+pub mod notification;
+use crate::{
+    behaviour::BehaviourConfig,
+    command_provider::CommandSpec,
+    events::{Delivery, Event},
+    ipc::commands::Command,
+    operator::OperatorState,
+    plugin::{self, PluginInfo, PluginReload, PluginScan},
+    scheduler::ScheduledEvent,
+    texture::TextureCacheStats,
+    transform::Transform,
+};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Stable, machine-readable error classification sent alongside every error response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
+pub enum ErrorCode {
+    SerdeError,
+    CommandRegistrationFailed,
+    CommandDeregistrationFailed,
+    CommandDoesNotExist,
+    MutexPoisoned,
+    ContextInvalid,
+    OperatorNotFound,
+    OperatorAlreadyExists,
+    SubscriptionNotFound,
+    InvalidSchedule,
+    ScheduleNotFound,
+    CommandFailed,
+    EventRejected,
+    SessionError,
+    PluginNotRegistered,
+    PluginAlreadyRegistered,
+    PluginFileNotFound,
+    SymbolNotFound,
+    UnsupportedCast,
+    IncompatiblePlugin,
+    InvalidManifest,
+    PluginInUse,
+    PluginPanicked,
+    InvalidAssets,
+    PluginError,
+}
+
+impl From<&commands::Error> for ErrorCode {
+    fn from(value: &commands::Error) -> Self {
+        match value {
+            commands::Error::SerdeError(_) => Self::SerdeError,
+            commands::Error::CommandRegistrationFailed(_) => Self::CommandRegistrationFailed,
+            commands::Error::CommandDeregistrationFailed(_) => Self::CommandDeregistrationFailed,
+            commands::Error::CommandDoesNotExist(_) => Self::CommandDoesNotExist,
+            commands::Error::MutexPoisoned(_) => Self::MutexPoisoned,
+            commands::Error::ContextInvalid(_) => Self::ContextInvalid,
+            commands::Error::OperatorNotFound(_) => Self::OperatorNotFound,
+            commands::Error::OperatorAlreadyExists(_) => Self::OperatorAlreadyExists,
+            commands::Error::InvalidSchedule(_) => Self::InvalidSchedule,
+            commands::Error::ScheduleNotFound(_) => Self::ScheduleNotFound,
+            commands::Error::CommandFailed(_) => Self::CommandFailed,
+            commands::Error::EventRejected(_) => Self::EventRejected,
+        }
+    }
+}
+
+impl From<&plugin::Error> for ErrorCode {
+    fn from(value: &plugin::Error) -> Self {
+        match value {
+            plugin::Error::PluginNotRegistered(_) => Self::PluginNotRegistered,
+            plugin::Error::PluginAlreadyRegistered(_) => Self::PluginAlreadyRegistered,
+            plugin::Error::PluginFileNotFound(_) => Self::PluginFileNotFound,
+            plugin::Error::SymbolNotFound(_) => Self::SymbolNotFound,
+            plugin::Error::UnsupportedCast(_) => Self::UnsupportedCast,
+            plugin::Error::IncompatiblePlugin(_) => Self::IncompatiblePlugin,
+            plugin::Error::InvalidManifest(_) => Self::InvalidManifest,
+            plugin::Error::PluginInUse(_) => Self::PluginInUse,
+            plugin::Error::PluginPanicked(_) => Self::PluginPanicked,
+            plugin::Error::CommandConflict(_) => Self::CommandRegistrationFailed,
+            plugin::Error::InvalidAssets(_) => Self::InvalidAssets,
+            plugin::Error::Other(_) => Self::PluginError,
+        }
+    }
+}
+
+/// Structured result data of a successful command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum Payload {
+    PluginLoaded {
+        name: String,
+        path: PathBuf,
+    },
+    PluginUnloaded {
+        name: String,
+        /// Operators retreated by a forced unload.
+        retreated: Vec<String>,
+    },
+    OperatorSpawned {
+        id: String,
+        plugin: String,
+        transform: Transform,
+    },
+    OperatorRetreated {
+        id: String,
+    },
+    EventScheduled {
+        event: Event,
+        /// Set if the client waited for the event to be handled.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delivery: Option<Delivery>,
+    },
+    Subscribed {
+        subscription_id: u64,
+    },
+    Unsubscribed {
+        subscription_ids: Vec<u64>,
+    },
+    Plugins {
+        plugins: Vec<PluginInfo>,
+    },
+    Operators {
+        operators: Vec<OperatorState>,
+    },
+    OperatorState {
+        state: OperatorState,
+    },
+    Transform {
+        id: String,
+        transform: Transform,
+    },
+    Behaviour {
+        id: String,
+        config: Option<BehaviourConfig>,
+    },
+    Scheduled {
+        scheduled: ScheduledEvent,
+    },
+    ScheduledEvents {
+        scheduled: Vec<ScheduledEvent>,
+    },
+    /// See [`TextureCache`](crate::texture::TextureCache) for which textures are counted.
+    TextureStats {
+        stats: TextureCacheStats,
+    },
+    ScheduleCancelled {
+        scheduled: ScheduledEvent,
+    },
+    SessionSaved {
+        name: String,
+        path: PathBuf,
+    },
+    PluginReloaded {
+        name: String,
+        #[serde(flatten)]
+        reload: PluginReload,
+    },
+    PluginsRescanned {
+        #[serde(flatten)]
+        scan: PluginScan,
+    },
+    SessionLoaded {
+        name: String,
+        errors: Vec<String>,
+    },
+    PluginCommandResult {
+        plugin: String,
+        name: String,
+        result: serde_json::Value,
+    },
+    Commands {
+        commands: Vec<CommandInfo>,
+    },
+}
+
+/// A command clients can send, as listed by `ListCommands`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandInfo {
+    /// Plugin that registered the command; `None` for built-in commands.
+    pub plugin: Option<String>,
+    #[serde(flatten)]
+    pub spec: CommandSpec,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
 pub enum Response {
-    Success(String),
-    Error(String),
+    Success {
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<Payload>,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+impl Response {
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::Success {
+            message: message.into(),
+            payload: None,
+        }
+    }
+
+    pub fn with_payload(message: impl Into<String>, payload: Payload) -> Self {
+        Self::Success {
+            message: message.into(),
+            payload: Some(payload),
+        }
+    }
+
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self::Error {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success { .. })
+    }
+}
+
+impl From<commands::Error> for Response {
+    fn from(value: commands::Error) -> Self {
+        Self::error(ErrorCode::from(&value), value.to_string())
+    }
+}
+
+impl From<plugin::Error> for Response {
+    fn from(value: plugin::Error) -> Self {
+        Self::error(ErrorCode::from(&value), value.to_string())
+    }
 }
 
 impl std::fmt::Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Success(m) => write!(f, "{}", m),
-            Self::Error(m) => write!(f, "{}", m),
+            Self::Success { message, .. } => write!(f, "{}", message),
+            Self::Error { code, message } => write!(f, "{:?}: {}", code, message),
         }
     }
 }
+
+/// Client-chosen correlation id, echoed back unchanged in the response. Any JSON scalar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    String(String),
+    Number(serde_json::Number),
+    Bool(bool),
+}
+
+/// A command as sent by a client, optionally tagged with a client-chosen correlation id.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestId>,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+impl Request {
+    pub fn from_json(value: &str) -> Result<Self, commands::Error> {
+        Ok(serde_json::from_str(value)?)
+    }
+
+    /// Parses and executes a request. Never fails: parse errors, and a command that panics,
+    /// are reported as an error response carrying the `request_id` if one could still be
+    /// recovered from the input. A `request_id` that is an array or object is rejected
+    /// without running the command.
+    pub fn execute_from_json(
+        value: &str,
+        ctx: &mut command_context::CommandContext,
+    ) -> ResponseEnvelope {
+        let mut value = match serde_json::from_str::<serde_json::Value>(value) {
+            Ok(value) => value,
+            Err(e) => {
+                return ResponseEnvelope {
+                    request_id: None,
+                    response: commands::Error::from(e).into(),
+                };
+            }
+        };
+        let request_id = value
+            .as_object_mut()
+            .and_then(|v| v.remove("request_id"))
+            .map(serde_json::from_value::<Option<RequestId>>)
+            .transpose();
+        let request_id = match request_id {
+            Ok(request_id) => request_id.flatten(),
+            Err(_) => {
+                return ResponseEnvelope {
+                    request_id: None,
+                    response: commands::Error::SerdeError(
+                        "request_id must be a string, number or boolean".to_owned(),
+                    )
+                    .into(),
+                };
+            }
+        };
+        let executed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            Command::execute_value(value, ctx).unwrap_or_else(Response::from)
+        }));
+        ResponseEnvelope {
+            request_id,
+            response: executed.unwrap_or_else(|payload| {
+                let message = plugin::panic_message(payload.as_ref());
+                tracing::error!("Command panicked: {}", message);
+                Response::error(
+                    ErrorCode::CommandFailed,
+                    format!("command panicked: {}", message),
+                )
+            }),
+        }
+    }
+}
+
+/// A response as sent back to the client.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseEnvelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestId>,
+    #[serde(flatten)]
+    pub response: Response,
+}
@@ -0,0 +1,185 @@
+use crate::events::{AnimationEvent, Delivery, Event};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+pub type NotificationSender = tokio::sync::broadcast::Sender<Notification>;
+
+/// Capacity of the broadcast channel notifications are pushed through; slow connections
+/// that fall further behind than this skip the oldest notifications.
+pub const NOTIFICATION_CAPACITY: usize = 256;
+
+/// Something that happened on the host which connected clients may subscribe to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+#[non_exhaustive]
+pub enum Notification {
+    Event {
+        event: Event,
+    },
+    /// An event that was not delivered, e.g. a scheduled event whose operator is gone.
+    EventUndelivered {
+        event: Event,
+        delivery: Delivery,
+    },
+    OperatorSpawned {
+        id: String,
+        plugin: String,
+    },
+    OperatorRetreated {
+        id: String,
+    },
+    OperatorArrived {
+        id: String,
+        position: (f32, f32),
+    },
+    /// An animation of the operator started, completed, ... or reached a keyed event.
+    AnimationEvent {
+        id: String,
+        event: AnimationEvent,
+    },
+    /// The operator's plugin panicked; the operator was removed.
+    OperatorFaulted {
+        id: String,
+        plugin: String,
+        error: String,
+    },
+    PluginLoaded {
+        name: String,
+    },
+    PluginUnloaded {
+        name: String,
+    },
+    /// The plugin panicked and was disabled; it stays registered until reloaded or unloaded.
+    PluginFaulted {
+        name: String,
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum NotificationKind {
+    Event,
+    EventUndelivered,
+    OperatorSpawned,
+    OperatorRetreated,
+    OperatorArrived,
+    AnimationEvent,
+    OperatorFaulted,
+    PluginLoaded,
+    PluginUnloaded,
+    PluginFaulted,
+}
+
+impl Notification {
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            Notification::Event { .. } => NotificationKind::Event,
+            Notification::EventUndelivered { .. } => NotificationKind::EventUndelivered,
+            Notification::OperatorSpawned { .. } => NotificationKind::OperatorSpawned,
+            Notification::OperatorRetreated { .. } => NotificationKind::OperatorRetreated,
+            Notification::OperatorArrived { .. } => NotificationKind::OperatorArrived,
+            Notification::AnimationEvent { .. } => NotificationKind::AnimationEvent,
+            Notification::OperatorFaulted { .. } => NotificationKind::OperatorFaulted,
+            Notification::PluginLoaded { .. } => NotificationKind::PluginLoaded,
+            Notification::PluginUnloaded { .. } => NotificationKind::PluginUnloaded,
+            Notification::PluginFaulted { .. } => NotificationKind::PluginFaulted,
+        }
+    }
+
+    pub fn operator_id(&self) -> Option<&str> {
+        match self {
+            Notification::Event { event } | Notification::EventUndelivered { event, .. } => {
+                Some(event.operator_id())
+            }
+            Notification::OperatorSpawned { id, .. } => Some(id),
+            Notification::OperatorRetreated { id } => Some(id),
+            Notification::OperatorArrived { id, .. } => Some(id),
+            Notification::AnimationEvent { id, .. } => Some(id),
+            Notification::OperatorFaulted { id, .. } => Some(id),
+            Notification::PluginLoaded { .. }
+            | Notification::PluginUnloaded { .. }
+            | Notification::PluginFaulted { .. } => None,
+        }
+    }
+}
+
+/// Topic filter of a single subscription. Unset fields match everything; when `operators`
+/// is set, notifications that do not concern an operator are filtered out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubscriptionFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operators: Option<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kinds: Option<HashSet<NotificationKind>>,
+    /// Names of `Event` variants (e.g. `"MoveTo"`), only applied to `Notification::Event`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<HashSet<String>>,
+}
+
+impl SubscriptionFilter {
+    pub fn matches(&self, notification: &Notification) -> bool {
+        if let Some(kinds) = &self.kinds
+            && !kinds.contains(&notification.kind())
+        {
+            return false;
+        }
+        if let Some(operators) = &self.operators
+            && !notification
+                .operator_id()
+                .is_some_and(|id| operators.contains(id))
+        {
+            return false;
+        }
+        if let (Some(events), Notification::Event { event }) = (&self.events, notification)
+            && !events.contains(event.kind())
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// The subscriptions held by a single connection.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    next_id: u64,
+    filters: BTreeMap<u64, SubscriptionFilter>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, filter: SubscriptionFilter) -> u64 {
+        self.next_id += 1;
+        self.filters.insert(self.next_id, filter);
+        self.next_id
+    }
+
+    pub fn unsubscribe(&mut self, id: u64) -> bool {
+        self.filters.remove(&id).is_some()
+    }
+
+    /// Removes all subscriptions and returns their ids.
+    pub fn clear(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.filters).into_keys().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Ids of all subscriptions whose filter matches the notification.
+    pub fn matching(&self, notification: &Notification) -> Vec<u64> {
+        self.filters
+            .iter()
+            .filter(|(_, filter)| filter.matches(notification))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// A notification as pushed to the client.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationEnvelope {
+    pub subscription_ids: Vec<u64>,
+    pub notification: Notification,
+}
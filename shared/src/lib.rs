@@ -1,7 +1,17 @@
+pub mod behaviour;
+pub mod command_provider;
+pub mod config;
+pub mod controller;
+pub mod effect;
 pub mod events;
 pub mod ipc;
 pub mod logging;
+pub mod movement;
 pub mod operator;
 pub mod plugin;
+pub mod scheduler;
+pub mod session;
 pub mod skin;
+pub mod spine;
 pub mod texture;
+pub mod transform;
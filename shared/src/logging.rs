@@ -1,15 +1,15 @@
-use tracing::level_filters::LevelFilter;
+use crate::config::LogConfig;
 use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
 static FILE_GUARD: std::sync::Mutex<
     Option<std::sync::Arc<std::sync::Mutex<tracing_appender::non_blocking::WorkerGuard>>>,
 > = std::sync::Mutex::new(None);
 
-pub fn init_logger() -> Result<(), Box<dyn std::error::Error>> {
+pub fn init_logger(config: &LogConfig) -> Result<(), Box<dyn std::error::Error>> {
     if FILE_GUARD.lock().unwrap().is_some() {
         return Ok(());
     }
-    std::fs::create_dir_all("logs/")?;
-    let file_appender = tracing_appender::rolling::daily("./logs/", "arkomp.log");
+    std::fs::create_dir_all(&config.dir)?;
+    let file_appender = tracing_appender::rolling::daily(&config.dir, "arkomp.log");
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
     let guard_arc = std::sync::Arc::new(std::sync::Mutex::new(guard));
     *FILE_GUARD.lock().unwrap() = Some(guard_arc.clone());
@@ -23,7 +23,7 @@ pub fn init_logger() -> Result<(), Box<dyn std::error::Error>> {
         .with_thread_names(true)
         .with_level(true)
         .with_thread_ids(true)
-        .with_filter(LevelFilter::DEBUG);
+        .with_filter(config.file_level()?);
 
     let stdout_layer = tracing_subscriber::fmt::layer()
         .with_writer(std::io::stdout)
@@ -34,7 +34,7 @@ pub fn init_logger() -> Result<(), Box<dyn std::error::Error>> {
         .with_thread_names(true)
         .with_level(true)
         .with_thread_ids(true)
-        .with_filter(LevelFilter::INFO);
+        .with_filter(config.stdout_level()?);
 
     tracing_subscriber::registry()
         .with(file_layer)
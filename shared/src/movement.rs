@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// Walking speed in pixels per second used when a `MoveTo` does not specify one.
+pub const DEFAULT_SPEED: f32 = 120.0;
+pub const MOVE_ANIMATION: &str = "Move";
+pub const IDLE_ANIMATION: &str = "Relax";
+
+/// Rejects speeds a walk could never finish at: zero, negative or not finite.
+pub fn check_speed(speed: f32) -> Result<(), String> {
+    if speed.is_finite() && speed > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("speed must be finite and positive, got {}", speed))
+    }
+}
+
+/// Rejects positions with a NaN or infinite coordinate.
+pub fn check_position(pos: (f32, f32)) -> Result<(), String> {
+    if pos.0.is_finite() && pos.1.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("position must be finite, got {:?}", pos))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps linear progress in `[0, 1]` onto eased progress in `[0, 1]`.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// An in-progress walk of an operator towards a target position.
+#[derive(Debug, Clone)]
+pub struct Motion {
+    from: (f32, f32),
+    to: (f32, f32),
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl Motion {
+    pub fn new(from: (f32, f32), to: (f32, f32), speed: f32, easing: Easing) -> Self {
+        let distance = (to.0 - from.0).hypot(to.1 - from.1);
+        Self {
+            from,
+            to,
+            duration: distance / speed.max(f32::EPSILON),
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    pub fn target(&self) -> (f32, f32) {
+        self.to
+    }
+
+    /// Whether the walk heads left, i.e. the operator has to face the other way.
+    pub fn heads_left(&self) -> bool {
+        self.to.0 < self.from.0
+    }
+
+    /// Advances the walk by `dt` seconds and returns the new position and whether the target
+    /// has been reached.
+    pub fn advance(&mut self, dt: f32) -> ((f32, f32), bool) {
+        self.elapsed += dt;
+        if self.elapsed >= self.duration {
+            return (self.to, true);
+        }
+        let t = self.easing.apply(self.elapsed / self.duration);
+        (
+            (
+                self.from.0 + (self.to.0 - self.from.0) * t,
+                self.from.1 + (self.to.1 - self.from.1) * t,
+            ),
+            false,
+        )
+    }
+}
@@ -1,8 +1,394 @@
+use crate::plugin::panic_message;
+use crate::{
+    behaviour::{
+        Action, Behaviour, BehaviourConfig, BehaviourState, SIT_ANIMATION, SLEEP_ANIMATION,
+    },
+    events::{AnimationEvent, Event},
+    movement::{DEFAULT_SPEED, Easing, IDLE_ANIMATION, MOVE_ANIMATION, Motion},
+    transform::Transform,
+};
+use eframe::egui::layers::{PaintList, ShapeIdx};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+use tracing::error;
+
 pub trait Operator: std::fmt::Debug + Send + Sync {
     fn render(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui);
     fn id(&self) -> String;
+    /// Plays `anim`. Besides animations requested by clients, the host starts its own idle,
+    /// move, sit, sleep and special animations, but only those the plugin manifest lists.
+    /// Implementations should still ignore names they do not know rather than panic.
     fn start_animation(&mut self, anim: &str);
     fn update_animation(&mut self, ctx: &eframe::egui::Context);
+    /// Animation events emitted since the last call, oldest first.
+    fn take_animation_events(&mut self) -> Vec<AnimationEvent> {
+        Vec::new()
+    }
     fn load_textures(&mut self, ctx: &eframe::egui::Context);
-    fn event_handler(&mut self, event: crate::events::Event);
+    /// Handles an event sent to the operator. An error rejects the event; the reason is
+    /// reported back to whoever sent it.
+    fn event_handler(&mut self, event: crate::events::Event) -> Result<(), String>;
+}
+
+/// Spawned operators keyed by their id.
+pub type OperatorRegistry = HashMap<String, OperatorInstance>;
+
+/// Ids of the operators built from `plugin`, sorted.
+pub fn instances_of(operators: &OperatorRegistry, plugin: &str) -> Vec<String> {
+    let mut ids: Vec<String> = operators
+        .values()
+        .filter(|op| op.state().plugin == plugin)
+        .map(|op| op.state().id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// Removes the operators quarantined after a panic in their plugin and returns them.
+pub fn take_faulted(operators: &mut OperatorRegistry) -> Vec<OperatorInstance> {
+    let ids: Vec<String> = operators
+        .values()
+        .filter(|op| op.fault().is_some())
+        .map(|op| op.state().id.clone())
+        .collect();
+    ids.iter().filter_map(|id| operators.remove(id)).collect()
+}
+
+/// Host-side view of a spawned operator, as reported to IPC clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperatorState {
+    pub id: String,
+    pub plugin: String,
+    pub animation: Option<String>,
+    pub skin: Option<String>,
+    pub transform: Transform,
+    pub behaviour: Option<BehaviourState>,
+}
+
+/// A spawned operator together with the state the host tracks for it.
+#[derive(Debug)]
+pub struct OperatorInstance {
+    operator: Box<dyn Operator>,
+    state: OperatorState,
+    motion: Option<Motion>,
+    behaviour: Option<Behaviour>,
+    /// Animations listed in the plugin manifest, see [`Self::start_host_animation`].
+    animations: Vec<String>,
+    /// Set when a call into the plugin panicked; the plugin is not called again.
+    fault: Option<String>,
+}
+
+impl OperatorInstance {
+    /// `animations` are the animations the plugin manifest lists.
+    pub fn new(
+        id: String,
+        plugin: String,
+        operator: Box<dyn Operator>,
+        transform: Transform,
+        animations: Vec<String>,
+    ) -> Self {
+        Self {
+            operator,
+            state: OperatorState {
+                id,
+                plugin,
+                animation: None,
+                skin: None,
+                transform,
+                behaviour: None,
+            },
+            motion: None,
+            behaviour: None,
+            animations,
+            fault: None,
+        }
+    }
+
+    /// Why the operator was quarantined, if it was.
+    pub fn fault(&self) -> Option<&str> {
+        self.fault.as_deref()
+    }
+
+    /// Calls into the plugin, quarantining the operator if the call panics.
+    /// Returns `None` if the operator has faulted, now or before.
+    fn call<R>(&mut self, method: &str, call: impl FnOnce(&mut dyn Operator) -> R) -> Option<R> {
+        if self.fault.is_some() {
+            return None;
+        }
+        let operator = self.operator.as_mut();
+        match catch_unwind(AssertUnwindSafe(|| call(operator))) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                let message = panic_message(payload.as_ref());
+                error!(
+                    "Operator {} of plugin {} panicked in {}: {}",
+                    self.state.id, self.state.plugin, method, message
+                );
+                self.fault = Some(format!("{} panicked: {}", method, message));
+                None
+            }
+        }
+    }
+
+    pub fn state(&self) -> &OperatorState {
+        &self.state
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.state.transform
+    }
+
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.state.transform
+    }
+
+    /// Renders the operator and moves everything it painted into place according to its
+    /// transform.
+    pub fn render(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui) {
+        let layer_id = ui.layer_id();
+        let start = ctx.graphics(|g| g.get(layer_id).map_or(ShapeIdx(0), PaintList::next_idx));
+        self.call("render", |op| op.render(ctx, ui));
+        let transform = self.state.transform;
+        ctx.graphics_mut(|g| {
+            let paint_list = g.entry(layer_id);
+            for idx in start.0..paint_list.next_idx().0 {
+                paint_list
+                    .mutate_shape(ShapeIdx(idx), |clipped| transform.apply(&mut clipped.shape));
+            }
+        });
+    }
+
+    pub fn start_animation(&mut self, anim: &str) {
+        self.call("start_animation", |op| op.start_animation(anim));
+        self.state.animation = Some(anim.to_owned());
+    }
+
+    /// Starts an animation the host picked itself rather than a client, e.g. to idle or walk.
+    /// Skipped unless the plugin manifest lists it, since the plugin may not have it.
+    pub fn start_host_animation(&mut self, anim: &str) {
+        if self.animations.iter().any(|known| known == anim) {
+            self.start_animation(anim);
+        }
+    }
+
+    /// Advances the animation and returns the animation events it emitted.
+    pub fn update_animation(&mut self, ctx: &eframe::egui::Context) -> Vec<AnimationEvent> {
+        self.call("update_animation", |op| op.update_animation(ctx));
+        self.call("take_animation_events", |op| op.take_animation_events())
+            .unwrap_or_default()
+    }
+
+    pub fn load_textures(&mut self, ctx: &eframe::egui::Context) {
+        self.call("load_textures", |op| op.load_textures(ctx));
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.motion.is_some()
+    }
+
+    /// Walks the operator towards `pos`, facing the direction of travel.
+    pub fn move_to(&mut self, pos: (f32, f32), speed: f32, easing: Easing) {
+        let motion = Motion::new(self.state.transform.position, pos, speed, easing);
+        self.state.transform.flip_x = motion.heads_left();
+        if !self.is_moving() {
+            self.start_host_animation(MOVE_ANIMATION);
+        }
+        self.motion = Some(motion);
+    }
+
+    /// Advances an ongoing walk by `dt` seconds. Returns `true` on the tick the operator
+    /// arrives, after switching it back to its idle animation.
+    pub fn update_motion(&mut self, dt: f32) -> bool {
+        let Some(motion) = &mut self.motion else {
+            return false;
+        };
+        let (position, arrived) = motion.advance(dt);
+        self.state.transform.position = position;
+        if arrived {
+            self.motion = None;
+            self.start_host_animation(IDLE_ANIMATION);
+        }
+        arrived
+    }
+
+    pub fn behaviour(&self) -> Option<&Behaviour> {
+        self.behaviour.as_ref()
+    }
+
+    /// Enables the autonomous behaviour engine, or disables it when `config` is `None`.
+    pub fn set_behaviour(&mut self, config: Option<BehaviourConfig>) {
+        self.behaviour = config.map(|config| Behaviour::new(config, &self.state.id));
+        self.state.behaviour = self.behaviour.as_ref().map(Behaviour::state);
+    }
+
+    /// Advances the behaviour engine by `dt` seconds and carries out whatever it decides.
+    /// Wander targets are kept inside `bounds` (min, max).
+    pub fn update_behaviour(&mut self, dt: f32, bounds: ((f32, f32), (f32, f32))) {
+        let busy = self.is_moving();
+        let position = self.state.transform.position;
+        let Some(behaviour) = &mut self.behaviour else {
+            return;
+        };
+        let Some(action) = behaviour.tick(dt, position, bounds, busy) else {
+            return;
+        };
+        self.state.behaviour = Some(behaviour.state());
+        let op_id = self.state.id.clone();
+        match action {
+            Action::Idle => self.start_host_animation(IDLE_ANIMATION),
+            Action::Wander {
+                target,
+                speed,
+                easing,
+            } => self.move_to(target, speed, easing),
+            Action::Sit => {
+                let _ = self.dispatch(Event::Sit { op_id });
+            }
+            Action::Sleep => {
+                let _ = self.dispatch(Event::Sleep { op_id });
+            }
+            Action::Special(anim) => self.start_host_animation(&anim),
+        }
+    }
+
+    /// Handles an event sent by a client. This counts as activity for the behaviour engine.
+    pub fn event_handler(&mut self, event: Event) -> Result<(), String> {
+        if let Some(behaviour) = &mut self.behaviour {
+            behaviour.touch();
+            self.state.behaviour = Some(behaviour.state());
+        }
+        self.dispatch(event)
+    }
+
+    /// Passes the event to the plugin and, unless it rejects the event, applies its effect on
+    /// the host side.
+    fn dispatch(&mut self, event: Event) -> Result<(), String> {
+        let handled = self.call("event_handler", |op| op.event_handler(event.clone()));
+        match handled {
+            Some(Ok(())) => {}
+            Some(Err(reason)) => return Err(reason),
+            None => return Err(self.fault.clone().unwrap_or_default()),
+        }
+        match &event {
+            Event::SetSkin { skin, .. } => self.state.skin = Some(skin.clone()),
+            Event::SetAnimation { ani, options, .. } if options.track == 0 && !options.queue => {
+                self.state.animation = Some(ani.clone())
+            }
+            Event::MoveTo {
+                pos, speed, easing, ..
+            } => self.move_to(*pos, speed.unwrap_or(DEFAULT_SPEED), *easing),
+            Event::Sit { .. } => self.start_host_animation(SIT_ANIMATION),
+            Event::Sleep { .. } => self.start_host_animation(SLEEP_ANIMATION),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Plays only `known` animations and panics on any other, like a careless plugin.
+    #[derive(Debug)]
+    struct Strict {
+        known: Vec<String>,
+        started: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Operator for Strict {
+        fn render(&mut self, _ctx: &eframe::egui::Context, _ui: &mut eframe::egui::Ui) {}
+        fn id(&self) -> String {
+            "strict".to_owned()
+        }
+        fn start_animation(&mut self, anim: &str) {
+            assert!(self.known.iter().any(|known| known == anim), "no {}", anim);
+            self.started.lock().unwrap().push(anim.to_owned());
+        }
+        fn update_animation(&mut self, _ctx: &eframe::egui::Context) {}
+        fn load_textures(&mut self, _ctx: &eframe::egui::Context) {}
+        fn event_handler(&mut self, _event: Event) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    /// An instance whose plugin has and lists `animations`, and the animations it started.
+    fn instance(animations: &[&str]) -> (OperatorInstance, Arc<Mutex<Vec<String>>>) {
+        let animations: Vec<String> = animations.iter().map(|a| a.to_string()).collect();
+        let started = Arc::default();
+        let operator = Strict {
+            known: animations.clone(),
+            started: Arc::clone(&started),
+        };
+        let instance = OperatorInstance::new(
+            "strict".to_owned(),
+            "strict".to_owned(),
+            Box::new(operator),
+            Transform::default(),
+            animations,
+        );
+        (instance, started)
+    }
+
+    #[test]
+    fn idle_animation_is_only_started_if_listed() {
+        let (mut op, started) = instance(&[]);
+        op.start_host_animation(IDLE_ANIMATION);
+        assert_eq!(op.fault(), None);
+        assert_eq!(op.state().animation, None);
+        assert!(started.lock().unwrap().is_empty());
+
+        let (mut op, started) = instance(&[IDLE_ANIMATION]);
+        op.start_host_animation(IDLE_ANIMATION);
+        assert_eq!(op.state().animation.as_deref(), Some(IDLE_ANIMATION));
+        assert_eq!(*started.lock().unwrap(), [IDLE_ANIMATION]);
+    }
+
+    #[test]
+    fn walking_only_starts_listed_animations() {
+        let (mut op, started) = instance(&[]);
+        op.move_to((10.0, 0.0), 100.0, Easing::Linear);
+        assert!(op.update_motion(1.0));
+        assert_eq!(op.fault(), None);
+        assert!(started.lock().unwrap().is_empty());
+
+        let (mut op, started) = instance(&[MOVE_ANIMATION, IDLE_ANIMATION]);
+        op.move_to((10.0, 0.0), 100.0, Easing::Linear);
+        assert!(op.update_motion(1.0));
+        assert_eq!(*started.lock().unwrap(), [MOVE_ANIMATION, IDLE_ANIMATION]);
+    }
+
+    #[test]
+    fn sit_and_sleep_only_start_listed_animations() {
+        let op_id = "strict".to_owned();
+        let (mut op, started) = instance(&[]);
+        assert_eq!(
+            op.event_handler(Event::Sit {
+                op_id: op_id.clone()
+            }),
+            Ok(())
+        );
+        assert_eq!(
+            op.event_handler(Event::Sleep {
+                op_id: op_id.clone()
+            }),
+            Ok(())
+        );
+        assert_eq!(op.fault(), None);
+        assert!(started.lock().unwrap().is_empty());
+
+        let (mut op, started) = instance(&[SIT_ANIMATION, SLEEP_ANIMATION]);
+        assert_eq!(
+            op.event_handler(Event::Sit {
+                op_id: op_id.clone()
+            }),
+            Ok(())
+        );
+        assert_eq!(op.event_handler(Event::Sleep { op_id }), Ok(()));
+        assert_eq!(*started.lock().unwrap(), [SIT_ANIMATION, SLEEP_ANIMATION]);
+    }
 }
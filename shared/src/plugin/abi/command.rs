@@ -0,0 +1,155 @@
+use super::{FfiStatus, FfiStr, Handle, VTable, destroy, guarded, last_panic, write_json};
+use crate::{
+    command_provider::{CommandProvider, CommandSpec},
+    plugin::{Error, PluginLibrary},
+};
+use serde_json::Value;
+use std::{ffi::c_void, sync::Arc};
+use tracing::debug;
+
+pub const COMMAND_VTABLE_SYMBOL: &[u8] = b"ARKOMP_COMMAND_VTABLE";
+
+/// Functions a command plugin exports. `this` is the handle returned by `create`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CommandVTable {
+    /// Returns null if the constructor panicked.
+    pub create: unsafe extern "C" fn() -> *mut c_void,
+    pub destroy: unsafe extern "C" fn(this: *mut c_void) -> FfiStatus,
+    /// Writes the [`CommandSpec`]s to `out` as a JSON array, valid until the next call on
+    /// `this`.
+    pub commands: unsafe extern "C" fn(this: *mut c_void, out: *mut FfiStr) -> FfiStatus,
+    /// `args` is JSON. Writes the outcome to `out` as a JSON `{"Ok": value}` or
+    /// `{"Err": message}`, valid until the next call on `this`.
+    pub execute: unsafe extern "C" fn(
+        this: *mut c_void,
+        command: FfiStr,
+        args: FfiStr,
+        out: *mut FfiStr,
+    ) -> FfiStatus,
+    /// Message of the last panic caught on `this`, valid until the next call on it.
+    pub last_panic: unsafe extern "C" fn(this: *mut c_void) -> FfiStr,
+}
+
+impl CommandVTable {
+    /// Builds the vtable for `T`, with `create` constructing the instance.
+    pub const fn new<T: CommandProvider>(create: unsafe extern "C" fn() -> *mut c_void) -> Self {
+        Self {
+            create,
+            destroy: destroy::<T>,
+            commands: commands::<T>,
+            execute: execute::<T>,
+            last_panic: last_panic::<T>,
+        }
+    }
+}
+
+impl VTable for CommandVTable {
+    fn destroy(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStatus {
+        self.destroy
+    }
+
+    fn last_panic(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStr {
+        self.last_panic
+    }
+}
+
+unsafe extern "C" fn commands<T: CommandProvider>(
+    this: *mut c_void,
+    out: *mut FfiStr,
+) -> FfiStatus {
+    unsafe {
+        guarded::<T>(this, |exported| {
+            let commands = exported.object.commands();
+            write_json(exported, &commands, out);
+        })
+    }
+}
+
+unsafe extern "C" fn execute<T: CommandProvider>(
+    this: *mut c_void,
+    command: FfiStr,
+    args: FfiStr,
+    out: *mut FfiStr,
+) -> FfiStatus {
+    let command = unsafe { command.as_str() }.unwrap_or_default();
+    let args = unsafe { args.as_str() }
+        .map(serde_json::from_str::<Value>)
+        .unwrap_or(Ok(Value::Null));
+    unsafe {
+        guarded::<T>(this, |exported| {
+            let result = match args {
+                Ok(args) => exported.object.execute(command, args),
+                Err(e) => Err(format!("Malformed arguments: {}", e)),
+            };
+            write_json(exported, &result, out);
+        })
+    }
+}
+
+/// Host-side command provider backed by a plugin's [`CommandVTable`].
+#[derive(Debug)]
+pub struct FfiCommandProvider {
+    handle: Handle<CommandVTable>,
+}
+
+impl FfiCommandProvider {
+    /// # Safety
+    /// `vtable` must come from `library`, which must have passed the version check.
+    pub(crate) unsafe fn new(
+        library: Arc<PluginLibrary>,
+        vtable: CommandVTable,
+    ) -> Result<Self, Error> {
+        let this = unsafe { (vtable.create)() };
+        let handle = unsafe { Handle::new(library, vtable, this) }?;
+        Ok(Self { handle })
+    }
+}
+
+impl CommandProvider for FfiCommandProvider {
+    fn commands(&self) -> Vec<CommandSpec> {
+        let mut out = FfiStr::NONE;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.commands)(h.this, &mut out) });
+        h.read_json(out).unwrap_or_else(|e| {
+            debug!("Dropping command specs the host cannot decode: {}", e);
+            Vec::new()
+        })
+    }
+
+    fn execute(&mut self, command: &str, args: Value) -> Result<Value, String> {
+        let args = args.to_string();
+        let mut out = FfiStr::NONE;
+        let h = &self.handle;
+        h.check(unsafe {
+            (h.vtable.execute)(h.this, FfiStr::new(command), FfiStr::new(&args), &mut out)
+        });
+        h.read_json::<Result<Value, String>>(out)?
+    }
+}
+
+/// Exports a command provider from a plugin library. `$constructor` is a
+/// `fn() -> $provider`.
+///
+/// ```ignore
+/// shared::export_commands!(Weather, Weather::new);
+/// ```
+#[macro_export]
+macro_rules! export_commands {
+    ($provider:ty, $constructor:path) => {
+        $crate::__export_declaration!();
+
+        #[unsafe(no_mangle)]
+        pub static ARKOMP_COMMAND_VTABLE: $crate::plugin::abi::CommandVTable =
+            $crate::plugin::abi::CommandVTable::new::<$provider>({
+                unsafe extern "C" fn create() -> *mut ::std::ffi::c_void {
+                    ::std::panic::catch_unwind(|| {
+                        let provider: $provider = $constructor();
+                        $crate::plugin::abi::into_handle(provider)
+                    })
+                    .unwrap_or(::std::ptr::null_mut())
+                }
+                create
+            });
+    };
+}
@@ -0,0 +1,144 @@
+use super::{FfiStatus, FfiStr, Handle, VTable, destroy, guarded, last_panic, write_json};
+use crate::{
+    controller::Controller,
+    ipc::{commands::Command, notification::Notification},
+    plugin::{Error, PluginLibrary},
+};
+use std::{ffi::c_void, sync::Arc};
+use tracing::debug;
+
+pub const CONTROLLER_VTABLE_SYMBOL: &[u8] = b"ARKOMP_CONTROLLER_VTABLE";
+
+/// Functions a controller plugin exports. `this` is the handle returned by `create`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ControllerVTable {
+    /// Returns null if the constructor panicked.
+    pub create: unsafe extern "C" fn() -> *mut c_void,
+    pub destroy: unsafe extern "C" fn(this: *mut c_void) -> FfiStatus,
+    /// `notification` is a [`Notification`] serialised as JSON.
+    pub notify: unsafe extern "C" fn(this: *mut c_void, notification: FfiStr) -> FfiStatus,
+    /// Writes the [`Command`]s to execute to `out` as a JSON array, valid until the next call
+    /// on `this`.
+    pub tick: unsafe extern "C" fn(this: *mut c_void, dt: f32, out: *mut FfiStr) -> FfiStatus,
+    /// Message of the last panic caught on `this`, valid until the next call on it.
+    pub last_panic: unsafe extern "C" fn(this: *mut c_void) -> FfiStr,
+}
+
+impl ControllerVTable {
+    /// Builds the vtable for `T`, with `create` constructing the instance.
+    pub const fn new<T: Controller>(create: unsafe extern "C" fn() -> *mut c_void) -> Self {
+        Self {
+            create,
+            destroy: destroy::<T>,
+            notify: notify::<T>,
+            tick: tick::<T>,
+            last_panic: last_panic::<T>,
+        }
+    }
+}
+
+impl VTable for ControllerVTable {
+    fn destroy(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStatus {
+        self.destroy
+    }
+
+    fn last_panic(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStr {
+        self.last_panic
+    }
+}
+
+unsafe extern "C" fn notify<T: Controller>(this: *mut c_void, notification: FfiStr) -> FfiStatus {
+    let Some(json) = (unsafe { notification.as_str() }) else {
+        return FfiStatus::Ok;
+    };
+    match serde_json::from_str::<Notification>(json) {
+        Ok(notification) => unsafe {
+            guarded::<T>(this, |exported| exported.object.notify(notification))
+        },
+        Err(e) => {
+            debug!("Dropping notification the plugin cannot decode: {}", e);
+            FfiStatus::Ok
+        }
+    }
+}
+
+unsafe extern "C" fn tick<T: Controller>(
+    this: *mut c_void,
+    dt: f32,
+    out: *mut FfiStr,
+) -> FfiStatus {
+    unsafe {
+        guarded::<T>(this, |exported| {
+            let commands = exported.object.tick(dt);
+            write_json(exported, &commands, out);
+        })
+    }
+}
+
+/// Host-side controller backed by a plugin's [`ControllerVTable`].
+#[derive(Debug)]
+pub struct FfiController {
+    handle: Handle<ControllerVTable>,
+}
+
+impl FfiController {
+    /// # Safety
+    /// `vtable` must come from `library`, which must have passed the version check.
+    pub(crate) unsafe fn new(
+        library: Arc<PluginLibrary>,
+        vtable: ControllerVTable,
+    ) -> Result<Self, Error> {
+        let this = unsafe { (vtable.create)() };
+        let handle = unsafe { Handle::new(library, vtable, this) }?;
+        Ok(Self { handle })
+    }
+}
+
+impl Controller for FfiController {
+    fn notify(&mut self, notification: Notification) {
+        match serde_json::to_string(&notification) {
+            Ok(json) => {
+                let h = &self.handle;
+                h.check(unsafe { (h.vtable.notify)(h.this, FfiStr::new(&json)) });
+            }
+            Err(e) => debug!("Failed to serialise notification for plugin: {}", e),
+        }
+    }
+
+    fn tick(&mut self, dt: f32) -> Vec<Command> {
+        let mut out = FfiStr::NONE;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.tick)(h.this, dt, &mut out) });
+        h.read_json(out).unwrap_or_else(|e| {
+            debug!("Dropping commands the host cannot decode: {}", e);
+            Vec::new()
+        })
+    }
+}
+
+/// Exports a controller type from a plugin library. `$constructor` is a
+/// `fn() -> $controller`.
+///
+/// ```ignore
+/// shared::export_controller!(Director, Director::new);
+/// ```
+#[macro_export]
+macro_rules! export_controller {
+    ($controller:ty, $constructor:path) => {
+        $crate::__export_declaration!();
+
+        #[unsafe(no_mangle)]
+        pub static ARKOMP_CONTROLLER_VTABLE: $crate::plugin::abi::ControllerVTable =
+            $crate::plugin::abi::ControllerVTable::new::<$controller>({
+                unsafe extern "C" fn create() -> *mut ::std::ffi::c_void {
+                    ::std::panic::catch_unwind(|| {
+                        let controller: $controller = $constructor();
+                        $crate::plugin::abi::into_handle(controller)
+                    })
+                    .unwrap_or(::std::ptr::null_mut())
+                }
+                create
+            });
+    };
+}
@@ -0,0 +1,135 @@
+use super::{FfiStatus, FfiStr, Handle, VTable, context, destroy, egui_ui, guarded, last_panic};
+use crate::{
+    effect::Effect,
+    plugin::{Error, PluginLibrary},
+};
+use std::{ffi::c_void, sync::Arc};
+
+pub const EFFECT_VTABLE_SYMBOL: &[u8] = b"ARKOMP_EFFECT_VTABLE";
+
+/// Functions an effect plugin exports. `this` is the handle returned by `create`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EffectVTable {
+    /// Returns null if the constructor panicked.
+    pub create: unsafe extern "C" fn() -> *mut c_void,
+    pub destroy: unsafe extern "C" fn(this: *mut c_void) -> FfiStatus,
+    pub update: unsafe extern "C" fn(this: *mut c_void, dt: f32) -> FfiStatus,
+    pub render_background:
+        unsafe extern "C" fn(this: *mut c_void, ctx: *const c_void, ui: *mut c_void) -> FfiStatus,
+    pub render_foreground:
+        unsafe extern "C" fn(this: *mut c_void, ctx: *const c_void, ui: *mut c_void) -> FfiStatus,
+    /// Message of the last panic caught on `this`, valid until the next call on it.
+    pub last_panic: unsafe extern "C" fn(this: *mut c_void) -> FfiStr,
+}
+
+impl EffectVTable {
+    /// Builds the vtable for `T`, with `create` constructing the instance.
+    pub const fn new<T: Effect>(create: unsafe extern "C" fn() -> *mut c_void) -> Self {
+        Self {
+            create,
+            destroy: destroy::<T>,
+            update: update::<T>,
+            render_background: render_background::<T>,
+            render_foreground: render_foreground::<T>,
+            last_panic: last_panic::<T>,
+        }
+    }
+}
+
+impl VTable for EffectVTable {
+    fn destroy(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStatus {
+        self.destroy
+    }
+
+    fn last_panic(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStr {
+        self.last_panic
+    }
+}
+
+unsafe extern "C" fn update<T: Effect>(this: *mut c_void, dt: f32) -> FfiStatus {
+    unsafe { guarded::<T>(this, |exported| exported.object.update(dt)) }
+}
+
+unsafe extern "C" fn render_background<T: Effect>(
+    this: *mut c_void,
+    ctx: *const c_void,
+    ui: *mut c_void,
+) -> FfiStatus {
+    let (ctx, ui) = unsafe { (context(ctx), egui_ui(ui)) };
+    unsafe { guarded::<T>(this, |exported| exported.object.render_background(ctx, ui)) }
+}
+
+unsafe extern "C" fn render_foreground<T: Effect>(
+    this: *mut c_void,
+    ctx: *const c_void,
+    ui: *mut c_void,
+) -> FfiStatus {
+    let (ctx, ui) = unsafe { (context(ctx), egui_ui(ui)) };
+    unsafe { guarded::<T>(this, |exported| exported.object.render_foreground(ctx, ui)) }
+}
+
+/// Host-side effect backed by a plugin's [`EffectVTable`].
+#[derive(Debug)]
+pub struct FfiEffect {
+    handle: Handle<EffectVTable>,
+}
+
+impl FfiEffect {
+    /// # Safety
+    /// `vtable` must come from `library`, which must have passed the version check.
+    pub(crate) unsafe fn new(
+        library: Arc<PluginLibrary>,
+        vtable: EffectVTable,
+    ) -> Result<Self, Error> {
+        let this = unsafe { (vtable.create)() };
+        let handle = unsafe { Handle::new(library, vtable, this) }?;
+        Ok(Self { handle })
+    }
+}
+
+impl Effect for FfiEffect {
+    fn update(&mut self, dt: f32) {
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.update)(h.this, dt) });
+    }
+
+    fn render_background(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui) {
+        let ctx: *const eframe::egui::Context = ctx;
+        let ui: *mut eframe::egui::Ui = ui;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.render_background)(h.this, ctx.cast(), ui.cast()) });
+    }
+
+    fn render_foreground(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui) {
+        let ctx: *const eframe::egui::Context = ctx;
+        let ui: *mut eframe::egui::Ui = ui;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.render_foreground)(h.this, ctx.cast(), ui.cast()) });
+    }
+}
+
+/// Exports an effect type from a plugin library. `$constructor` is a `fn() -> $effect`.
+///
+/// ```ignore
+/// shared::export_effect!(Snow, Snow::new);
+/// ```
+#[macro_export]
+macro_rules! export_effect {
+    ($effect:ty, $constructor:path) => {
+        $crate::__export_declaration!();
+
+        #[unsafe(no_mangle)]
+        pub static ARKOMP_EFFECT_VTABLE: $crate::plugin::abi::EffectVTable =
+            $crate::plugin::abi::EffectVTable::new::<$effect>({
+                unsafe extern "C" fn create() -> *mut ::std::ffi::c_void {
+                    ::std::panic::catch_unwind(|| {
+                        let effect: $effect = $constructor();
+                        $crate::plugin::abi::into_handle(effect)
+                    })
+                    .unwrap_or(::std::ptr::null_mut())
+                }
+                create
+            });
+    };
+}
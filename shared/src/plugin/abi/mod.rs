@@ -0,0 +1,350 @@
+//! Stable entry point between the host and dynamically loaded plugins.
+//!
+//! Every plugin library exports a [`PluginDeclaration`] under [`DECLARATION_SYMBOL`], which the
+//! host checks before touching anything else in the library, and the vtable of its kind: an
+//! [`OperatorVTable`], [`EffectVTable`], [`ControllerVTable`] or [`CommandVTable`]. Both are
+//! generated by the kind's export macro, e.g. [`export_operator!`](crate::export_operator).
+//!
+//! Only `#[repr(C)]` types and `extern "C"` functions cross the boundary. Strings are passed as
+//! [`FfiStr`] and everything structured as JSON. Panics are caught on the plugin side and
+//! reported as [`FfiStatus::Panicked`], since unwinding across `extern "C"` aborts the process.
+//! The egui `Context` and `Ui` are the exception: they are passed as pointers to Rust types, so
+//! the plugin must be built by the same compiler against the same egui as the host. The
+//! declaration carries a fingerprint of both, see [`PluginDeclaration::build`].
+mod command;
+mod controller;
+mod effect;
+mod operator;
+
+pub use command::{COMMAND_VTABLE_SYMBOL, CommandVTable, FfiCommandProvider};
+pub use controller::{CONTROLLER_VTABLE_SYMBOL, ControllerVTable, FfiController};
+pub use effect::{EFFECT_VTABLE_SYMBOL, EffectVTable, FfiEffect};
+pub use operator::{FfiOperator, OPERATOR_VTABLE_SYMBOL, OperatorVTable};
+
+use crate::plugin::{Error, PluginLibrary, panic_message};
+use std::{
+    ffi::c_void,
+    fmt,
+    panic::{AssertUnwindSafe, catch_unwind, resume_unwind},
+    sync::Arc,
+};
+
+/// Bumped whenever the layout of [`PluginDeclaration`] or one of the vtables changes, or the
+/// JSON passed through them does.
+pub const ABI_VERSION: u32 = 8;
+
+pub const DECLARATION_SYMBOL: &[u8] = b"ARKOMP_PLUGIN_DECLARATION";
+
+/// The compiler this crate was built with, as set by `build.rs`.
+pub const RUSTC_VERSION: &str = env!("ARKOMP_RUSTC_VERSION");
+
+/// The egui and eframe version this crate is built against. The workspace pins both to exactly
+/// this version, which a test checks against the resolved dependency graph.
+pub const EGUI_VERSION: &str = "0.32.3";
+
+/// Versions a plugin was built against. `abi_version` stays the first field in every ABI
+/// version, so the host can read it before trusting the rest of the layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub api_major: u32,
+    pub api_minor: u32,
+    pub api_patch: u32,
+    /// Hash of [`RUSTC_VERSION`], [`EGUI_VERSION`] and of the size and alignment of the egui types passed by pointer.
+    pub build: u64,
+}
+
+impl PluginDeclaration {
+    /// The versions of this build of the crate.
+    pub const CURRENT: Self = Self {
+        abi_version: ABI_VERSION,
+        api_major: parse_version(env!("CARGO_PKG_VERSION_MAJOR")),
+        api_minor: parse_version(env!("CARGO_PKG_VERSION_MINOR")),
+        api_patch: parse_version(env!("CARGO_PKG_VERSION_PATCH")),
+        build: build_fingerprint(),
+    };
+
+    /// Whether a plugin declaring `self` can be loaded by this host. The ABI version and build
+    /// fingerprint must match exactly and the API version must be semver compatible.
+    pub fn is_compatible(&self) -> bool {
+        self.abi_version == ABI_VERSION
+            && self.build == Self::CURRENT.build
+            && is_api_compatible(self.api_major, self.api_minor)
+    }
+}
+
+impl fmt::Display for PluginDeclaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "abi {}, api {}.{}.{}, build {:016x}",
+            self.abi_version, self.api_major, self.api_minor, self.api_patch, self.build
+        )
+    }
+}
+
+/// Whether a plugin built against API `major.minor` can run on this host.
+pub fn is_api_compatible(major: u32, minor: u32) -> bool {
+    let current = PluginDeclaration::CURRENT;
+    major == current.api_major && (current.api_major != 0 || minor == current.api_minor)
+}
+
+/// FNV-1a over [`RUSTC_VERSION`], [`EGUI_VERSION`] and the layout of `egui::Context` and
+/// `egui::Ui`. The layout is redundant with the versions unless rustc's could not be determined.
+const fn build_fingerprint() -> u64 {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let layout = [
+        size_of::<eframe::egui::Context>(),
+        align_of::<eframe::egui::Context>(),
+        size_of::<eframe::egui::Ui>(),
+        align_of::<eframe::egui::Ui>(),
+    ];
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let versions = [RUSTC_VERSION.as_bytes(), EGUI_VERSION.as_bytes()];
+    let mut i = 0;
+    while i < versions.len() {
+        let mut j = 0;
+        while j < versions[i].len() {
+            hash = (hash ^ versions[i][j] as u64).wrapping_mul(PRIME);
+            j += 1;
+        }
+        // Separator, so the boundary between the two strings is part of the hash.
+        hash = (hash ^ 0xff).wrapping_mul(PRIME);
+        i += 1;
+    }
+    let mut i = 0;
+    while i < layout.len() {
+        hash = (hash ^ layout[i] as u64).wrapping_mul(PRIME);
+        i += 1;
+    }
+    hash
+}
+
+const fn parse_version(value: &str) -> u32 {
+    let bytes = value.as_bytes();
+    let mut result = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        result = result * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    result
+}
+
+/// Borrowed UTF-8 string. A null `ptr` stands for `None`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiStr {
+    ptr: *const u8,
+    len: usize,
+}
+
+impl FfiStr {
+    pub const NONE: Self = Self {
+        ptr: std::ptr::null(),
+        len: 0,
+    };
+
+    pub fn new(value: &str) -> Self {
+        Self {
+            ptr: value.as_ptr(),
+            len: value.len(),
+        }
+    }
+
+    pub fn from_option(value: Option<&str>) -> Self {
+        value.map_or(Self::NONE, Self::new)
+    }
+
+    /// # Safety
+    /// `ptr` must be null or point to `len` bytes that stay valid for `'a`.
+    pub unsafe fn as_str<'a>(self) -> Option<&'a str> {
+        if self.ptr.is_null() {
+            return None;
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr, self.len) };
+        std::str::from_utf8(bytes).ok()
+    }
+}
+
+/// Outcome of a call into a plugin.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiStatus {
+    Ok,
+    /// The call panicked; `last_panic` has the message.
+    Panicked,
+}
+
+/// Plugin-side storage behind a handle.
+struct Exported<T> {
+    object: T,
+    /// Backs strings returned to the host, valid until the next call.
+    out: String,
+    panic: String,
+}
+
+/// Boxes `object` into a handle for a vtable's `create`.
+pub fn into_handle<T>(object: T) -> *mut c_void {
+    Box::into_raw(Box::new(Exported {
+        object,
+        out: String::new(),
+        panic: String::new(),
+    }))
+    .cast()
+}
+
+unsafe fn exported<'a, T>(this: *mut c_void) -> &'a mut Exported<T> {
+    unsafe { &mut *this.cast::<Exported<T>>() }
+}
+
+/// Runs `call` on the object behind `this`, catching any panic.
+unsafe fn guarded<T>(this: *mut c_void, call: impl FnOnce(&mut Exported<T>)) -> FfiStatus {
+    match catch_unwind(AssertUnwindSafe(|| call(unsafe { exported::<T>(this) }))) {
+        Ok(()) => FfiStatus::Ok,
+        Err(payload) => {
+            unsafe { exported::<T>(this) }.panic = panic_message(payload.as_ref());
+            FfiStatus::Panicked
+        }
+    }
+}
+
+/// Hands `value` to the host through `out`, serialised as JSON.
+unsafe fn write_json<T, V: serde::Serialize>(
+    exported: &mut Exported<T>,
+    value: &V,
+    out: *mut FfiStr,
+) {
+    exported.out = serde_json::to_string(value).unwrap_or_default();
+    unsafe { *out = FfiStr::new(&exported.out) };
+}
+
+unsafe extern "C" fn destroy<T>(this: *mut c_void) -> FfiStatus {
+    match catch_unwind(AssertUnwindSafe(|| {
+        drop(unsafe { Box::from_raw(this.cast::<Exported<T>>()) })
+    })) {
+        Ok(()) => FfiStatus::Ok,
+        Err(_) => FfiStatus::Panicked,
+    }
+}
+
+unsafe extern "C" fn last_panic<T>(this: *mut c_void) -> FfiStr {
+    FfiStr::new(&unsafe { exported::<T>(this) }.panic)
+}
+
+unsafe fn context<'a>(ctx: *const c_void) -> &'a eframe::egui::Context {
+    unsafe { &*ctx.cast::<eframe::egui::Context>() }
+}
+
+unsafe fn egui_ui<'a>(ui: *mut c_void) -> &'a mut eframe::egui::Ui {
+    unsafe { &mut *ui.cast::<eframe::egui::Ui>() }
+}
+
+/// Functions every vtable has.
+pub(crate) trait VTable: Copy + fmt::Debug {
+    fn destroy(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStatus;
+    fn last_panic(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStr;
+}
+
+/// Host-side handle to an object created by a plugin. Keeps the library loaded for as long as
+/// the object lives.
+#[derive(Debug)]
+pub(crate) struct Handle<V: VTable> {
+    vtable: V,
+    this: *mut c_void,
+    // Dropped after `destroy` ran in `Drop::drop`.
+    _library: Arc<PluginLibrary>,
+}
+
+// Objects behind a handle implement one of the plugin traits, which are `Send + Sync`.
+unsafe impl<V: VTable> Send for Handle<V> {}
+unsafe impl<V: VTable> Sync for Handle<V> {}
+
+impl<V: VTable> Handle<V> {
+    /// # Safety
+    /// `vtable` must come from `library`, which must have passed the version check, and
+    /// `this` must have been returned by its `create`.
+    unsafe fn new(
+        library: Arc<PluginLibrary>,
+        vtable: V,
+        this: *mut c_void,
+    ) -> Result<Self, Error> {
+        if this.is_null() {
+            return Err(Error::PluginPanicked(
+                "constructor panicked while creating a plugin object".to_owned(),
+            ));
+        }
+        Ok(Self {
+            vtable,
+            this,
+            _library: library,
+        })
+    }
+
+    /// Turns a panic the plugin caught into one on the host side, so callers handle plugin
+    /// and host panics alike.
+    fn check(&self, status: FfiStatus) {
+        if status == FfiStatus::Panicked {
+            let message = unsafe { (self.vtable.last_panic())(self.this).as_str() }
+                .unwrap_or_default()
+                .to_owned();
+            resume_unwind(Box::new(message));
+        }
+    }
+
+    /// Reads a JSON value the plugin wrote to `out`.
+    fn read_json<T: serde::de::DeserializeOwned>(&self, out: FfiStr) -> Result<T, String> {
+        let json = unsafe { out.as_str() }.unwrap_or_default();
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+impl<V: VTable> Drop for Handle<V> {
+    fn drop(&mut self) {
+        if unsafe { (self.vtable.destroy())(self.this) } == FfiStatus::Panicked {
+            tracing::debug!("Plugin object panicked while being destroyed");
+        }
+    }
+}
+
+/// Exports the [`PluginDeclaration`] of this build. Used by the `export_*` macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __export_declaration {
+    () => {
+        #[unsafe(no_mangle)]
+        pub static ARKOMP_PLUGIN_DECLARATION: $crate::plugin::abi::PluginDeclaration =
+            $crate::plugin::abi::PluginDeclaration::CURRENT;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn egui_version_matches_the_resolved_dependencies() {
+        let output = Command::new(env!("CARGO"))
+            .args(["metadata", "--format-version", "1", "--offline"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .expect("cargo metadata runs");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let packages = metadata["packages"].as_array().unwrap();
+        for name in ["egui", "eframe"] {
+            let versions: Vec<_> = packages
+                .iter()
+                .filter(|package| package["name"] == name)
+                .map(|package| package["version"].as_str().unwrap())
+                .collect();
+            assert_eq!(versions, [EGUI_VERSION], "resolved {} versions", name);
+        }
+    }
+}
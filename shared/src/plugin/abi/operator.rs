@@ -0,0 +1,246 @@
+use super::{
+    FfiStatus, FfiStr, Handle, VTable, context, destroy, egui_ui, guarded, last_panic, write_json,
+};
+use crate::{
+    events::{AnimationEvent, Event},
+    operator::Operator,
+    plugin::{Error, PluginLibrary},
+};
+use std::{ffi::c_void, sync::Arc};
+use tracing::debug;
+
+pub const OPERATOR_VTABLE_SYMBOL: &[u8] = b"ARKOMP_OPERATOR_VTABLE";
+
+/// Functions an operator plugin exports. `this` is the handle returned by `create`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct OperatorVTable {
+    /// Returns null if the constructor panicked.
+    pub create: unsafe extern "C" fn(id: FfiStr) -> *mut c_void,
+    pub destroy: unsafe extern "C" fn(this: *mut c_void) -> FfiStatus,
+    /// The string written to `out` stays valid until the next call on `this`.
+    pub id: unsafe extern "C" fn(this: *mut c_void, out: *mut FfiStr) -> FfiStatus,
+    pub render:
+        unsafe extern "C" fn(this: *mut c_void, ctx: *const c_void, ui: *mut c_void) -> FfiStatus,
+    pub start_animation: unsafe extern "C" fn(this: *mut c_void, anim: FfiStr) -> FfiStatus,
+    pub update_animation: unsafe extern "C" fn(this: *mut c_void, ctx: *const c_void) -> FfiStatus,
+    /// Writes the animation events emitted since the last call to `out` as a JSON array of
+    /// [`AnimationEvent`], valid until the next call on `this`.
+    pub take_animation_events:
+        unsafe extern "C" fn(this: *mut c_void, out: *mut FfiStr) -> FfiStatus,
+    pub load_textures: unsafe extern "C" fn(this: *mut c_void, ctx: *const c_void) -> FfiStatus,
+    /// `event` is an [`Event`] serialised as JSON. Writes the outcome to `out` as a JSON
+    /// `{"Ok": null}` or `{"Err": reason}`, valid until the next call on `this`.
+    pub event_handler:
+        unsafe extern "C" fn(this: *mut c_void, event: FfiStr, out: *mut FfiStr) -> FfiStatus,
+    /// Message of the last panic caught on `this`, valid until the next call on it.
+    pub last_panic: unsafe extern "C" fn(this: *mut c_void) -> FfiStr,
+}
+
+impl OperatorVTable {
+    /// Builds the vtable for `T`, with `create` constructing the instances.
+    pub const fn new<T: Operator>(create: unsafe extern "C" fn(id: FfiStr) -> *mut c_void) -> Self {
+        Self {
+            create,
+            destroy: destroy::<T>,
+            id: id::<T>,
+            render: render::<T>,
+            start_animation: start_animation::<T>,
+            update_animation: update_animation::<T>,
+            take_animation_events: take_animation_events::<T>,
+            load_textures: load_textures::<T>,
+            event_handler: event_handler::<T>,
+            last_panic: last_panic::<T>,
+        }
+    }
+}
+
+impl VTable for OperatorVTable {
+    fn destroy(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStatus {
+        self.destroy
+    }
+
+    fn last_panic(&self) -> unsafe extern "C" fn(this: *mut c_void) -> FfiStr {
+        self.last_panic
+    }
+}
+
+unsafe extern "C" fn id<T: Operator>(this: *mut c_void, out: *mut FfiStr) -> FfiStatus {
+    unsafe {
+        guarded::<T>(this, |exported| {
+            exported.out = exported.object.id();
+            *out = FfiStr::new(&exported.out);
+        })
+    }
+}
+
+unsafe extern "C" fn render<T: Operator>(
+    this: *mut c_void,
+    ctx: *const c_void,
+    ui: *mut c_void,
+) -> FfiStatus {
+    let (ctx, ui) = unsafe { (context(ctx), egui_ui(ui)) };
+    unsafe { guarded::<T>(this, |exported| exported.object.render(ctx, ui)) }
+}
+
+unsafe extern "C" fn start_animation<T: Operator>(this: *mut c_void, anim: FfiStr) -> FfiStatus {
+    let Some(anim) = (unsafe { anim.as_str() }) else {
+        return FfiStatus::Ok;
+    };
+    unsafe { guarded::<T>(this, |exported| exported.object.start_animation(anim)) }
+}
+
+unsafe extern "C" fn update_animation<T: Operator>(
+    this: *mut c_void,
+    ctx: *const c_void,
+) -> FfiStatus {
+    let ctx = unsafe { context(ctx) };
+    unsafe { guarded::<T>(this, |exported| exported.object.update_animation(ctx)) }
+}
+
+unsafe extern "C" fn take_animation_events<T: Operator>(
+    this: *mut c_void,
+    out: *mut FfiStr,
+) -> FfiStatus {
+    unsafe {
+        guarded::<T>(this, |exported| {
+            let events = exported.object.take_animation_events();
+            write_json(exported, &events, out);
+        })
+    }
+}
+
+unsafe extern "C" fn load_textures<T: Operator>(
+    this: *mut c_void,
+    ctx: *const c_void,
+) -> FfiStatus {
+    let ctx = unsafe { context(ctx) };
+    unsafe { guarded::<T>(this, |exported| exported.object.load_textures(ctx)) }
+}
+
+unsafe extern "C" fn event_handler<T: Operator>(
+    this: *mut c_void,
+    event: FfiStr,
+    out: *mut FfiStr,
+) -> FfiStatus {
+    let event = unsafe { event.as_str() }
+        .ok_or_else(|| "no event".to_owned())
+        .and_then(|json| serde_json::from_str::<Event>(json).map_err(|e| e.to_string()));
+    unsafe {
+        guarded::<T>(this, |exported| {
+            let result = match event {
+                Ok(event) => exported.object.event_handler(event),
+                Err(e) => {
+                    debug!("Rejecting event the plugin cannot decode: {}", e);
+                    Err(format!("Plugin cannot decode event: {}", e))
+                }
+            };
+            write_json(exported, &result, out);
+        })
+    }
+}
+
+/// Host-side operator backed by a plugin's [`OperatorVTable`].
+#[derive(Debug)]
+pub struct FfiOperator {
+    handle: Handle<OperatorVTable>,
+}
+
+impl FfiOperator {
+    /// # Safety
+    /// `vtable` must come from `library`, which must have passed the version check.
+    pub(crate) unsafe fn new(
+        library: Arc<PluginLibrary>,
+        vtable: OperatorVTable,
+        id: Option<&str>,
+    ) -> Result<Self, Error> {
+        let this = unsafe { (vtable.create)(FfiStr::from_option(id)) };
+        let handle = unsafe { Handle::new(library, vtable, this) }?;
+        Ok(Self { handle })
+    }
+}
+
+impl Operator for FfiOperator {
+    fn render(&mut self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui) {
+        let ctx: *const eframe::egui::Context = ctx;
+        let ui: *mut eframe::egui::Ui = ui;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.render)(h.this, ctx.cast(), ui.cast()) });
+    }
+
+    fn id(&self) -> String {
+        let mut id = FfiStr::NONE;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.id)(h.this, &mut id) });
+        unsafe { id.as_str() }.unwrap_or_default().to_owned()
+    }
+
+    fn start_animation(&mut self, anim: &str) {
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.start_animation)(h.this, FfiStr::new(anim)) });
+    }
+
+    fn update_animation(&mut self, ctx: &eframe::egui::Context) {
+        let ctx: *const eframe::egui::Context = ctx;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.update_animation)(h.this, ctx.cast()) });
+    }
+
+    fn take_animation_events(&mut self) -> Vec<AnimationEvent> {
+        let mut out = FfiStr::NONE;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.take_animation_events)(h.this, &mut out) });
+        h.read_json(out).unwrap_or_else(|e| {
+            debug!("Dropping animation events the host cannot decode: {}", e);
+            Vec::new()
+        })
+    }
+
+    fn load_textures(&mut self, ctx: &eframe::egui::Context) {
+        let ctx: *const eframe::egui::Context = ctx;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.load_textures)(h.this, ctx.cast()) });
+    }
+
+    fn event_handler(&mut self, event: Event) -> Result<(), String> {
+        let json = serde_json::to_string(&event).map_err(|e| {
+            debug!("Failed to serialise event for plugin: {}", e);
+            format!("Cannot serialise event: {}", e)
+        })?;
+        let mut out = FfiStr::NONE;
+        let h = &self.handle;
+        h.check(unsafe { (h.vtable.event_handler)(h.this, FfiStr::new(&json), &mut out) });
+        h.read_json::<Result<(), String>>(out)?
+    }
+}
+
+/// Exports an operator type from a plugin library.
+///
+/// `$constructor` is a `fn(Option<String>) -> $operator`, called with the id requested by the
+/// host, if any.
+///
+/// ```ignore
+/// shared::export_operator!(MyOperator, MyOperator::new);
+/// ```
+#[macro_export]
+macro_rules! export_operator {
+    ($operator:ty, $constructor:path) => {
+        $crate::__export_declaration!();
+
+        #[unsafe(no_mangle)]
+        pub static ARKOMP_OPERATOR_VTABLE: $crate::plugin::abi::OperatorVTable =
+            $crate::plugin::abi::OperatorVTable::new::<$operator>({
+                unsafe extern "C" fn create(
+                    id: $crate::plugin::abi::FfiStr,
+                ) -> *mut ::std::ffi::c_void {
+                    let id = unsafe { id.as_str() }.map(str::to_owned);
+                    ::std::panic::catch_unwind(move || {
+                        let operator: $operator = $constructor(id);
+                        $crate::plugin::abi::into_handle(operator)
+                    })
+                    .unwrap_or(::std::ptr::null_mut())
+                }
+                create
+            });
+    };
+}
@@ -0,0 +1,105 @@
+use crate::{
+    events,
+    plugin::{Error, PluginKind, abi},
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Metadata shipped next to a plugin library, in a RON file with the library's stem and a
+/// `.ron` extension (`libfoo.so` is described by `libfoo.ron`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Default registry name when the client does not pick one.
+    pub id: String,
+    /// Human readable name, for plugin pickers.
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub kind: PluginKind,
+    #[serde(default)]
+    pub skins: Vec<String>,
+    /// Animations the plugin's operators can play. The host only starts its own idle, move,
+    /// sit, sleep and special animations on an operator if they are listed here.
+    #[serde(default)]
+    pub animations: Vec<String>,
+    /// Names of the custom events the plugin's operators accept, see
+    /// [`crate::events::Event::CustomEvent`].
+    #[serde(default)]
+    pub custom_events: Vec<String>,
+    /// Host API the plugin requires, as `major.minor` or `major.minor.patch`.
+    pub api_version: String,
+}
+
+impl PluginManifest {
+    pub fn accepts_event(&self, name: &str) -> bool {
+        self.custom_events.iter().any(|event| event == name)
+    }
+
+    pub fn path_for(library: &Path) -> PathBuf {
+        library.with_extension("ron")
+    }
+
+    /// Reads the manifest belonging to `library` and checks it describes a compatible plugin
+    /// of `kind`.
+    pub fn load(library: &Path, kind: PluginKind) -> Result<Self, Error> {
+        let manifest = Self::read(library)?;
+        manifest.validate(kind)?;
+        Ok(manifest)
+    }
+
+    /// Reads the manifest belonging to `library` and checks it describes a compatible plugin
+    /// of any kind.
+    pub fn read(library: &Path) -> Result<Self, Error> {
+        let path = Self::path_for(library);
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            debug!("Failed to read manifest {}: {}", path.display(), e);
+            Error::InvalidManifest(format!("{}: {}", path.display(), e))
+        })?;
+        let manifest: Self = ron::from_str(&text).map_err(|e| {
+            debug!("Failed to parse manifest {}: {}", path.display(), e);
+            Error::InvalidManifest(format!("{}: {}", path.display(), e))
+        })?;
+        manifest.validate(manifest.kind)?;
+        Ok(manifest)
+    }
+
+    pub fn validate(&self, kind: PluginKind) -> Result<(), Error> {
+        if self.id.is_empty() {
+            return Err(Error::InvalidManifest("id must not be empty".to_owned()));
+        }
+        if self.kind != kind {
+            return Err(Error::InvalidManifest(format!(
+                "{} is a {:?} plugin, expected {:?}",
+                self.id, self.kind, kind
+            )));
+        }
+        if let Some(name) = self
+            .custom_events
+            .iter()
+            .find(|name| !events::is_namespaced(name))
+        {
+            return Err(Error::InvalidManifest(format!(
+                "{} declares custom event {:?}, which is not namespaced as namespace.event",
+                self.id, name
+            )));
+        }
+        let mut parts = self.api_version.split('.').map(str::parse::<u32>);
+        let (Some(Ok(major)), Some(Ok(minor))) = (parts.next(), parts.next()) else {
+            return Err(Error::InvalidManifest(format!(
+                "{} has malformed api_version {:?}",
+                self.id, self.api_version
+            )));
+        };
+        if !abi::is_api_compatible(major, minor) {
+            return Err(Error::IncompatiblePlugin(format!(
+                "{} requires host api {}, host is {}",
+                self.id,
+                self.api_version,
+                abi::PluginDeclaration::CURRENT
+            )));
+        }
+        Ok(())
+    }
+}
@@ -1,19 +1,69 @@
 // shared/plugin/mod.rs
+pub mod abi;
+pub mod manifest;
 pub mod types;
+use crate::ipc::{command_context::CommandContext, commands::Command};
+use abi::PluginDeclaration;
 use libloading::{Library, Symbol};
+use manifest::PluginManifest;
+use serde::{Deserialize, Serialize};
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    env::consts::DLL_EXTENSION,
     fmt,
     path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::SystemTime,
 };
 use tracing::debug;
+use types::command_plugin::CommandPlugin;
 
 pub trait Plugin: fmt::Debug + Send + Sync + Any {
     fn name(&self) -> &str;
+    fn path(&self) -> &Path;
+    fn kind(&self) -> PluginKind;
+    fn manifest(&self) -> &PluginManifest;
+    /// Modification time of the library when it was loaded.
+    fn modified(&self) -> Option<SystemTime>;
+    /// Loads a fresh copy of this plugin's library under the same name.
+    fn reload(&self) -> Result<Box<dyn Plugin>, Error>;
+    /// Why the plugin was disabled, if it panicked. A faulted plugin stays registered but is
+    /// no longer called until it is reloaded.
+    fn fault(&self) -> Option<String> {
+        None
+    }
     fn as_any(&self) -> &dyn Any;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum PluginKind {
+    Operator,
+    /// Particles, weather overlays and other visuals drawn around the operators.
+    Effect,
+    /// Headless logic that reacts to notifications by issuing commands.
+    Controller,
+    /// Adds IPC commands.
+    Command,
+    /// An operator built by the host from a Spine atlas and skeleton, without a library.
+    Spine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: PluginKind,
+    pub manifest: PluginManifest,
+    /// Why the plugin was disabled, if it panicked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault: Option<String>,
+}
+
 pub fn cast_plugin_to<P: Plugin>(plugin: &dyn Plugin) -> Result<&P, Error> {
     Ok(plugin.as_any().downcast_ref::<P>().ok_or_else(|| {
         debug!(
@@ -29,19 +79,114 @@ pub fn cast_plugin_to<P: Plugin>(plugin: &dyn Plugin) -> Result<&P, Error> {
     })?)
 }
 
+/// Message of a caught panic.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_owned())
+}
+
 #[derive(Debug)]
 pub(crate) struct PluginLibrary {
     pub(crate) library: Library,
     path: PathBuf,
+    modified: Option<SystemTime>,
+    /// Private copy the library was opened from, removed on drop.
+    copy: Option<PathBuf>,
 }
 
+static COPY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 impl PluginLibrary {
+    /// Opens the library and checks its declared versions before anything else in it is used.
     pub fn new(path: &Path) -> Result<Self, Error> {
-        let library: Library = unsafe { Library::new(path) }?;
+        Self::open(path, None)
+    }
+
+    /// Like [`Self::new`], but opens a private copy of the file. The dynamic loader hands out
+    /// the already loaded library for a path it has seen, so this is how a rebuilt library
+    /// gets loaded while the old one is still in use.
+    pub fn new_copy(path: &Path) -> Result<Self, Error> {
+        let dir = std::env::temp_dir().join("arkomp-plugins");
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let copy = dir.join(format!(
+            "{}-{}-{}.{}",
+            stem,
+            std::process::id(),
+            COPY_COUNTER.fetch_add(1, Ordering::Relaxed),
+            DLL_EXTENSION
+        ));
+        std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::copy(path, &copy))
+            .map_err(|e| {
+                debug!("Failed to copy {} for reloading: {}", path.display(), e);
+                Error::Other(format!("Cannot copy {}: {}", path.display(), e))
+            })?;
+        let result = Self::open(path, Some(copy.clone()));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&copy);
+        }
+        result
+    }
+
+    fn open(path: &Path, copy: Option<PathBuf>) -> Result<Self, Error> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let library: Library = unsafe { Library::new(copy.as_deref().unwrap_or(path)) }?;
+        let declaration =
+            match unsafe { library.get::<*const PluginDeclaration>(abi::DECLARATION_SYMBOL) } {
+                Ok(symbol) => {
+                    // Older declarations are smaller, only their first field is safe to read
+                    // before the ABI version is known.
+                    let abi_version = unsafe { *symbol.cast::<u32>() };
+                    if abi_version != abi::ABI_VERSION {
+                        debug!(
+                            "{} was built for abi {}, host is {}",
+                            path.display(),
+                            abi_version,
+                            PluginDeclaration::CURRENT
+                        );
+                        return Err(Error::IncompatiblePlugin(format!(
+                            "{} was built for abi {}, host is {}",
+                            path.display(),
+                            abi_version,
+                            PluginDeclaration::CURRENT
+                        )));
+                    }
+                    unsafe { **symbol }
+                }
+                Err(_) => {
+                    debug!("{} does not declare a plugin version", path.display());
+                    return Err(Error::IncompatiblePlugin(format!(
+                        "{} does not export a plugin declaration",
+                        path.display()
+                    )));
+                }
+            };
+        if !declaration.is_compatible() {
+            debug!(
+                "{} was built for {}, host is {}",
+                path.display(),
+                declaration,
+                PluginDeclaration::CURRENT
+            );
+            return Err(Error::IncompatiblePlugin(format!(
+                "{} was built for {}, host is {} ({}, egui {}); plugins must be built with the same \
+                 compiler and egui as the host",
+                path.display(),
+                declaration,
+                PluginDeclaration::CURRENT,
+                abi::RUSTC_VERSION,
+                abi::EGUI_VERSION
+            )));
+        }
 
         Ok(Self {
             library,
             path: path.to_path_buf(),
+            modified,
+            copy,
         })
     }
 
@@ -53,15 +198,34 @@ impl PluginLibrary {
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+impl Drop for PluginLibrary {
+    fn drop(&mut self) {
+        if let Some(copy) = &self.copy {
+            let _ = std::fs::remove_file(copy);
+        }
+    }
 }
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
     PluginNotRegistered(String),
+    PluginAlreadyRegistered(String),
     PluginFileNotFound(libloading::Error),
     SymbolNotFound(libloading::Error),
     UnsupportedCast(String),
+    IncompatiblePlugin(String),
+    InvalidManifest(String),
+    PluginInUse(String),
+    PluginPanicked(String),
+    CommandConflict(String),
+    InvalidAssets(String),
     Other(String),
 }
 
@@ -69,9 +233,16 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::PluginNotRegistered(e) => write!(f, "Plugin is not in registry: {}", e),
+            Error::PluginAlreadyRegistered(e) => write!(f, "Plugin is already registered: {}", e),
             Error::PluginFileNotFound(e) => write!(f, "Plugin file not found: {}", e),
             Error::SymbolNotFound(e) => write!(f, "Symbol not found: {}", e),
             Error::UnsupportedCast(e) => write!(f, "Cast is not supported: {}", e),
+            Error::IncompatiblePlugin(e) => write!(f, "Plugin is incompatible: {}", e),
+            Error::InvalidManifest(e) => write!(f, "Invalid plugin manifest: {}", e),
+            Error::PluginInUse(e) => write!(f, "Plugin is in use: {}", e),
+            Error::PluginPanicked(e) => write!(f, "Plugin panicked: {}", e),
+            Error::CommandConflict(e) => write!(f, "Plugin command conflicts: {}", e),
+            Error::InvalidAssets(e) => write!(f, "Invalid Spine assets: {}", e),
             Error::Other(e) => write!(f, "Other error: {}", e),
         }
     }
@@ -105,20 +276,178 @@ impl From<&str> for Error {
     }
 }
 
+/// Outcome of scanning the plugin search paths, by plugin name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginScan {
+    /// Plugins found and loaded by this scan.
+    pub added: Vec<String>,
+    /// Loaded plugins whose library was modified since it was loaded.
+    pub changed: Vec<String>,
+    /// Loaded plugins whose library no longer exists.
+    pub missing: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Outcome of reloading a plugin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginReload {
+    /// Operators that were respawned from the new library.
+    pub respawned: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl PluginReload {
+    /// Reloads plugin `name` from its library file and respawns its operators with their
+    /// transform, skin, animation and behaviour. The old library stays loaded if this fails.
+    pub fn run(ctx: &mut CommandContext, name: &str) -> Result<Self, Error> {
+        crate::ipc::commands::reload_plugin(ctx, name)
+    }
+}
+
+impl PluginScan {
+    /// Scans the search paths of the context's registry and loads every new plugin.
+    pub fn run(ctx: &mut CommandContext) -> Self {
+        crate::ipc::commands::rescan_plugins(ctx)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct PluginRegistry {
     plugins: HashMap<String, Box<dyn Plugin>>,
+    search_paths: Vec<PathBuf>,
+    copy_libraries: bool,
+    /// Plugins whose fault was returned by [`Self::take_new_faults`].
+    reported_faults: Mutex<HashSet<String>>,
 }
 
 impl PluginRegistry {
     pub fn new() -> Self {
         Self {
             plugins: HashMap::new(),
+            search_paths: Vec::new(),
+            copy_libraries: false,
+            reported_faults: Mutex::default(),
         }
     }
 
-    pub(crate) fn register_plugin(&mut self, name: String, plugin: Box<dyn Plugin>) {
+    pub fn with_search_paths(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            plugins: HashMap::new(),
+            search_paths,
+            copy_libraries: false,
+            reported_faults: Mutex::default(),
+        }
+    }
+
+    /// Whether plugins are loaded from a private copy of their library. Required for hot
+    /// reloading, since overwriting a library that is mapped into the process crashes it.
+    pub fn copy_libraries(&self) -> bool {
+        self.copy_libraries
+    }
+
+    pub fn set_copy_libraries(&mut self, copy_libraries: bool) {
+        self.copy_libraries = copy_libraries;
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    /// Resolves a relative plugin path against the search paths. Paths that are absolute,
+    /// exist relative to the working directory or are not found anywhere are returned as is.
+    pub fn resolve_path(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() || path.exists() {
+            return path.to_path_buf();
+        }
+        self.search_paths
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.exists())
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Lists the plugin libraries in the search paths and their subdirectories. Only libraries
+    /// with a manifest next to them are returned, along with Spine atlases that have a
+    /// skeleton next to them.
+    pub fn discover(&self) -> Vec<PathBuf> {
+        fn walk(dir: &Path, found: &mut Vec<PathBuf>) {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                debug!("Cannot read plugin directory {}", dir.display());
+                return;
+            };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.is_dir() {
+                    // Skins of a Spine plugin are not plugins of their own.
+                    if !crate::spine::is_skins_dir(&path) {
+                        walk(&path, found);
+                    }
+                } else if path.extension().is_some_and(|ext| ext == DLL_EXTENSION)
+                    && PluginManifest::path_for(&path).is_file()
+                    || crate::spine::is_atlas(&path)
+                        && crate::spine::SpineAssets::for_atlas(&path).is_some()
+                {
+                    found.push(path);
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        for dir in &self.search_paths {
+            walk(dir, &mut found);
+        }
+        found.sort();
+        found
+    }
+
+    /// Adds `plugin` under `name`. Fails if a plugin of that name is registered; it has to be
+    /// unloaded first, or swapped with [`Self::replace_plugin`].
+    pub(crate) fn register_plugin(
+        &mut self,
+        name: String,
+        plugin: Box<dyn Plugin>,
+    ) -> Result<(), Error> {
+        self.check_unregistered(&name)?;
         self.plugins.insert(name, plugin);
+        Ok(())
+    }
+
+    /// Fails if a plugin named `name` is registered.
+    pub(crate) fn check_unregistered(&self, name: &str) -> Result<(), Error> {
+        match self.plugins.get(name) {
+            Some(registered) => {
+                debug!("Plugin {} is already registered", name);
+                Err(Error::PluginAlreadyRegistered(format!(
+                    "Plugin {} is already registered from {}",
+                    name,
+                    registered.path().display()
+                )))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Registers `plugin` under `name`, returning the plugin it replaces. Used by reloads.
+    pub(crate) fn replace_plugin(
+        &mut self,
+        name: String,
+        plugin: Box<dyn Plugin>,
+    ) -> Option<Box<dyn Plugin>> {
+        self.reported_faults.lock().unwrap().remove(&name);
+        self.plugins.insert(name, plugin)
+    }
+
+    /// Plugins that faulted since the last call, with the reason, each returned once per load.
+    pub fn take_new_faults(&self) -> Vec<(String, String)> {
+        let mut reported = self.reported_faults.lock().unwrap();
+        let mut faults: Vec<_> = self
+            .plugins
+            .iter()
+            .filter(|(name, _)| !reported.contains(*name))
+            .filter_map(|(name, plugin)| Some((name.clone(), plugin.fault()?)))
+            .collect();
+        faults.sort();
+        reported.extend(faults.iter().map(|(name, _)| name.clone()));
+        faults
     }
 
     pub fn get_plugin(&self, name: &str) -> Result<&dyn Plugin, Error> {
@@ -129,6 +458,7 @@ impl PluginRegistry {
     }
 
     pub(crate) fn deregister_plugin(&mut self, name: &str) -> Result<Box<dyn Plugin>, Error> {
+        self.reported_faults.lock().unwrap().remove(name);
         self.plugins.remove(name).ok_or_else(|| {
             debug!("Plugin {} is not registered", name);
             Error::PluginNotRegistered(format!("Plugin {} is not registered", name))
@@ -139,7 +469,71 @@ impl PluginRegistry {
         self.plugins.is_empty()
     }
 
+    /// The command plugin that registered `command`, if any.
+    pub fn command_owner(&self, command: &str) -> Option<&CommandPlugin> {
+        self.plugins_of::<CommandPlugin>()
+            .into_iter()
+            .find(|plugin| plugin.command(command).is_some())
+    }
+
+    /// Checks that the commands of `plugin`, about to be registered as `name`, clash neither
+    /// with each other, a built-in command nor a command of another plugin.
+    pub(crate) fn check_commands(&self, name: &str, plugin: &dyn Plugin) -> Result<(), Error> {
+        let Some(plugin) = plugin.as_any().downcast_ref::<CommandPlugin>() else {
+            return Ok(());
+        };
+        for (i, spec) in plugin.commands().iter().enumerate() {
+            let conflict = if Command::NAMES.contains(&spec.name.as_str()) {
+                Some("a built-in command".to_owned())
+            } else if plugin.commands()[..i].iter().any(|s| s.name == spec.name) {
+                Some("another command of the plugin".to_owned())
+            } else {
+                self.command_owner(&spec.name)
+                    .filter(|owner| owner.name() != name)
+                    .map(|owner| format!("a command of plugin {}", owner.name()))
+            };
+            if let Some(conflict) = conflict {
+                debug!(
+                    "Command {} of plugin {} clashes with {}",
+                    spec.name, name, conflict
+                );
+                return Err(Error::CommandConflict(format!(
+                    "{} of plugin {} clashes with {}",
+                    spec.name, name, conflict
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Every registered plugin of type `P`, sorted by name.
+    pub fn plugins_of<P: Plugin>(&self) -> Vec<&P> {
+        let mut plugins: Vec<(&String, &P)> = self
+            .plugins
+            .iter()
+            .filter_map(|(name, plugin)| Some((name, plugin.as_any().downcast_ref::<P>()?)))
+            .collect();
+        plugins.sort_by(|a, b| a.0.cmp(b.0));
+        plugins.into_iter().map(|(_, plugin)| plugin).collect()
+    }
+
     pub fn plugin_list(&self) -> Vec<String> {
         self.plugins.keys().cloned().collect()
     }
+
+    pub fn plugin_infos(&self) -> Vec<PluginInfo> {
+        let mut infos: Vec<PluginInfo> = self
+            .plugins
+            .iter()
+            .map(|(name, plugin)| PluginInfo {
+                name: name.clone(),
+                path: plugin.path().to_path_buf(),
+                kind: plugin.kind(),
+                manifest: plugin.manifest().clone(),
+                fault: plugin.fault(),
+            })
+            .collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
 }
@@ -0,0 +1,130 @@
+use crate::{
+    command_provider::{CommandProvider, CommandSpec},
+    ipc::commands,
+    plugin::{
+        Error, Plugin, PluginKind,
+        abi::{COMMAND_VTABLE_SYMBOL, CommandVTable, FfiCommandProvider},
+        manifest::PluginManifest,
+        types::{Guarded, open_library},
+    },
+};
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Adds IPC commands. Clients send them like built-in commands, tagged with their name, or
+/// through [`crate::ipc::commands::Command::PluginCommand`]. The command specs are read once,
+/// when the plugin is loaded.
+#[derive(Debug)]
+pub struct CommandPlugin {
+    provider: Guarded<FfiCommandProvider>,
+    commands: Vec<CommandSpec>,
+    manifest: PluginManifest,
+    name: String,
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl CommandPlugin {
+    pub fn new(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, false)
+    }
+
+    pub fn new_copy(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, true)
+    }
+
+    pub(crate) fn open(path: &Path, name: Option<String>, copy: bool) -> Result<Self, Error> {
+        let (manifest, name, library, vtable) = open_library::<CommandVTable>(
+            path,
+            name,
+            copy,
+            PluginKind::Command,
+            COMMAND_VTABLE_SYMBOL,
+        )?;
+        let (path, modified) = (library.path().clone(), library.modified());
+        let provider = Guarded::new(unsafe { FfiCommandProvider::new(Arc::new(library), vtable) }?);
+        let commands = provider
+            .call(&name, "commands", |p| p.commands())
+            .ok_or_else(|| Error::PluginPanicked(format!("{} panicked listing commands", name)))?;
+        Ok(Self {
+            provider,
+            commands,
+            manifest,
+            name,
+            path,
+            modified,
+        })
+    }
+
+    pub fn commands(&self) -> &[CommandSpec] {
+        &self.commands
+    }
+
+    pub fn command(&self, command: &str) -> Option<&CommandSpec> {
+        self.commands.iter().find(|spec| spec.name == command)
+    }
+
+    /// Checks `args` against the command's schema and executes it. This is the only place
+    /// arguments are validated, so every caller gets the check.
+    pub fn execute(&self, command: &str, args: Value) -> Result<Value, commands::Error> {
+        let spec = self.command(command).ok_or_else(|| {
+            commands::Error::CommandDoesNotExist(format!(
+                "Plugin {} has no command {}",
+                self.name, command
+            ))
+        })?;
+        spec.validate(&args).map_err(commands::Error::SerdeError)?;
+        self.provider
+            .call(&self.name, "execute", |p| p.execute(command, args))
+            .unwrap_or_else(|| {
+                Err(format!(
+                    "Plugin {} is disabled: {}",
+                    self.name,
+                    self.provider.fault().unwrap_or_default()
+                ))
+            })
+            .map_err(|e| {
+                commands::Error::CommandFailed(format!("{}.{}: {}", self.name, command, e))
+            })
+    }
+}
+
+impl Plugin for CommandPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn kind(&self) -> PluginKind {
+        PluginKind::Command
+    }
+
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    fn modified(&self) -> Option<std::time::SystemTime> {
+        self.modified
+    }
+
+    fn reload(&self) -> Result<Box<dyn Plugin>, Error> {
+        Ok(Box::new(Self::new_copy(
+            &self.path,
+            Some(self.name.clone()),
+        )?))
+    }
+
+    fn fault(&self) -> Option<String> {
+        self.provider.fault()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
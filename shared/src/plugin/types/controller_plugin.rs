@@ -0,0 +1,103 @@
+use crate::{
+    controller::Controller,
+    ipc::{commands::Command, notification::Notification},
+    plugin::{
+        Error, Plugin, PluginKind,
+        abi::{CONTROLLER_VTABLE_SYMBOL, ControllerVTable, FfiController},
+        manifest::PluginManifest,
+        types::{Guarded, open_library},
+    },
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Headless behaviour logic. The host feeds the controller notifications and ticks it; the
+/// commands it returns are executed as if a client had sent them.
+#[derive(Debug)]
+pub struct ControllerPlugin {
+    controller: Guarded<FfiController>,
+    manifest: PluginManifest,
+    name: String,
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl ControllerPlugin {
+    pub fn new(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, false)
+    }
+
+    pub fn new_copy(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, true)
+    }
+
+    pub(crate) fn open(path: &Path, name: Option<String>, copy: bool) -> Result<Self, Error> {
+        let (manifest, name, library, vtable) = open_library::<ControllerVTable>(
+            path,
+            name,
+            copy,
+            PluginKind::Controller,
+            CONTROLLER_VTABLE_SYMBOL,
+        )?;
+        let (path, modified) = (library.path().clone(), library.modified());
+        let controller = unsafe { FfiController::new(Arc::new(library), vtable) }?;
+        Ok(Self {
+            controller: Guarded::new(controller),
+            manifest,
+            name,
+            path,
+            modified,
+        })
+    }
+
+    pub fn notify(&self, notification: Notification) {
+        self.controller
+            .call(&self.name, "notify", |c| c.notify(notification));
+    }
+
+    /// Advances the controller by `dt` seconds and returns the commands it wants executed.
+    pub fn tick(&self, dt: f32) -> Vec<Command> {
+        self.controller
+            .call(&self.name, "tick", |c| c.tick(dt))
+            .unwrap_or_default()
+    }
+}
+
+impl Plugin for ControllerPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn kind(&self) -> PluginKind {
+        PluginKind::Controller
+    }
+
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    fn modified(&self) -> Option<std::time::SystemTime> {
+        self.modified
+    }
+
+    fn reload(&self) -> Result<Box<dyn Plugin>, Error> {
+        Ok(Box::new(Self::new_copy(
+            &self.path,
+            Some(self.name.clone()),
+        )?))
+    }
+
+    fn fault(&self) -> Option<String> {
+        self.controller.fault()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
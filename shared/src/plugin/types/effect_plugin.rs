@@ -0,0 +1,106 @@
+use crate::{
+    effect::Effect,
+    plugin::{
+        Error, Plugin, PluginKind,
+        abi::{EFFECT_VTABLE_SYMBOL, EffectVTable, FfiEffect},
+        manifest::PluginManifest,
+        types::{Guarded, open_library},
+    },
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A visual effect drawn behind and in front of the operators, such as particles or a
+/// weather overlay. The plugin owns a single effect, created when it is loaded.
+#[derive(Debug)]
+pub struct EffectPlugin {
+    effect: Guarded<FfiEffect>,
+    manifest: PluginManifest,
+    name: String,
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl EffectPlugin {
+    pub fn new(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, false)
+    }
+
+    pub fn new_copy(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, true)
+    }
+
+    pub(crate) fn open(path: &Path, name: Option<String>, copy: bool) -> Result<Self, Error> {
+        let (manifest, name, library, vtable) = open_library::<EffectVTable>(
+            path,
+            name,
+            copy,
+            PluginKind::Effect,
+            EFFECT_VTABLE_SYMBOL,
+        )?;
+        let (path, modified) = (library.path().clone(), library.modified());
+        let effect = unsafe { FfiEffect::new(Arc::new(library), vtable) }?;
+        Ok(Self {
+            effect: Guarded::new(effect),
+            manifest,
+            name,
+            path,
+            modified,
+        })
+    }
+
+    pub fn update(&self, dt: f32) {
+        self.effect.call(&self.name, "update", |e| e.update(dt));
+    }
+
+    pub fn render_background(&self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui) {
+        self.effect.call(&self.name, "render_background", |e| {
+            e.render_background(ctx, ui)
+        });
+    }
+
+    pub fn render_foreground(&self, ctx: &eframe::egui::Context, ui: &mut eframe::egui::Ui) {
+        self.effect.call(&self.name, "render_foreground", |e| {
+            e.render_foreground(ctx, ui)
+        });
+    }
+}
+
+impl Plugin for EffectPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn kind(&self) -> PluginKind {
+        PluginKind::Effect
+    }
+
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    fn modified(&self) -> Option<std::time::SystemTime> {
+        self.modified
+    }
+
+    fn reload(&self) -> Result<Box<dyn Plugin>, Error> {
+        Ok(Box::new(Self::new_copy(
+            &self.path,
+            Some(self.name.clone()),
+        )?))
+    }
+
+    fn fault(&self) -> Option<String> {
+        self.effect.fault()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
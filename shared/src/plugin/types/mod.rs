@@ -1 +1,159 @@
+pub mod command_plugin;
+pub mod controller_plugin;
+pub mod effect_plugin;
 pub mod operator_plugin;
+pub mod spine_plugin;
+
+use crate::{
+    operator::Operator,
+    plugin::{
+        Error, Plugin, PluginKind, PluginLibrary, cast_plugin_to, manifest::PluginManifest,
+        panic_message,
+    },
+    spine,
+};
+use command_plugin::CommandPlugin;
+use controller_plugin::ControllerPlugin;
+use effect_plugin::EffectPlugin;
+use operator_plugin::OperatorPlugin;
+use spine_plugin::SpinePlugin;
+use std::{
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::Path,
+    sync::Mutex,
+};
+use tracing::{debug, error};
+
+/// Loads the plugin at `path` as whatever kind its manifest declares, registered as `name` or,
+/// if `None`, its manifest id. With `copy`, a private copy of the library is loaded so the
+/// file can be replaced while the plugin is loaded. A Spine atlas is loaded as a
+/// [`SpinePlugin`].
+pub fn load(path: &Path, name: Option<String>, copy: bool) -> Result<Box<dyn Plugin>, Error> {
+    if spine::is_atlas(path) {
+        return Ok(Box::new(SpinePlugin::open(path, name)?));
+    }
+    let manifest = PluginManifest::read(path)?;
+    Ok(match manifest.kind {
+        PluginKind::Operator => Box::new(OperatorPlugin::open(path, name, copy)?),
+        PluginKind::Effect => Box::new(EffectPlugin::open(path, name, copy)?),
+        PluginKind::Controller => Box::new(ControllerPlugin::open(path, name, copy)?),
+        PluginKind::Command => Box::new(CommandPlugin::open(path, name, copy)?),
+        PluginKind::Spine => {
+            return Err(Error::InvalidManifest(format!(
+                "{} is a Spine plugin, load its atlas instead",
+                manifest.id
+            )));
+        }
+    })
+}
+
+/// The manifest of the plugin at `path`, which for a Spine atlas may be synthesised.
+pub fn read_manifest(path: &Path) -> Result<PluginManifest, Error> {
+    if spine::is_atlas(path) {
+        spine_plugin::read_manifest(path)
+    } else {
+        PluginManifest::read(path)
+    }
+}
+
+/// Modification time of the files the plugin at `path` is loaded from.
+pub fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    if spine::is_atlas(path) {
+        spine_plugin::modified(path)
+    } else {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// Builds an operator from an operator or Spine plugin.
+pub fn build_operator(plugin: &dyn Plugin, id: Option<String>) -> Result<Box<dyn Operator>, Error> {
+    if let Some(plugin) = plugin.as_any().downcast_ref::<SpinePlugin>() {
+        return plugin.build(id);
+    }
+    cast_plugin_to::<OperatorPlugin>(plugin)?.build(id)
+}
+
+/// Everything a plugin of `kind` needs: its validated manifest, registry name, library and
+/// the vtable exported as `symbol`.
+pub(crate) fn open_library<V: Copy>(
+    path: &Path,
+    name: Option<String>,
+    copy: bool,
+    kind: PluginKind,
+    symbol: &[u8],
+) -> Result<(PluginManifest, String, PluginLibrary, V), Error> {
+    let manifest = PluginManifest::load(path, kind)?;
+    let name = name.unwrap_or_else(|| manifest.id.clone());
+    let library = if copy {
+        PluginLibrary::new_copy(path)
+    } else {
+        PluginLibrary::new(path)
+    };
+    let library = match library {
+        Ok(v) => v,
+        Err(e) => {
+            debug!(
+                "Failed to load library {}@[{}]: {}",
+                name,
+                path.display(),
+                e
+            );
+            Err(e)?
+        }
+    };
+    let vtable = match library.load_symbol::<*const V>(symbol) {
+        Ok(v) => unsafe { **v },
+        Err(e) => {
+            debug!(
+                "Failed to load {:?} vtable in {}@[{}]",
+                kind,
+                name,
+                path.display()
+            );
+            Err(e)?
+        }
+    };
+    Ok((manifest, name, library, vtable))
+}
+
+/// The single instance owned by an effect, controller or command plugin. A panic in any call
+/// disables the instance instead of unwinding into the host.
+#[derive(Debug)]
+pub(crate) struct Guarded<T> {
+    slot: Mutex<(T, Option<String>)>,
+}
+
+impl<T> Guarded<T> {
+    pub(crate) fn new(object: T) -> Self {
+        Self {
+            slot: Mutex::new((object, None)),
+        }
+    }
+
+    /// Runs `f` on the instance, or returns `None` if it has faulted, now or before.
+    pub(crate) fn call<R>(
+        &self,
+        plugin: &str,
+        method: &str,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Option<R> {
+        let mut slot = self.slot.lock().unwrap();
+        let (object, fault) = &mut *slot;
+        if fault.is_some() {
+            return None;
+        }
+        match catch_unwind(AssertUnwindSafe(|| f(object))) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                let message = format!("{} panicked: {}", method, panic_message(payload.as_ref()));
+                error!("Plugin {} disabled: {}", plugin, message);
+                *fault = Some(message);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn fault(&self) -> Option<String> {
+        self.slot.lock().unwrap().1.clone()
+    }
+}
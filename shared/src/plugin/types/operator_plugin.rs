@@ -1,48 +1,57 @@
-use tracing::debug;
-
 use crate::{
     operator::Operator,
-    plugin::{Error, Plugin, PluginLibrary},
+    plugin::{
+        Error, Plugin, PluginKind, PluginLibrary,
+        abi::{FfiOperator, OPERATOR_VTABLE_SYMBOL, OperatorVTable},
+        manifest::PluginManifest,
+        types::open_library,
+    },
 };
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
 #[derive(Debug)]
 pub struct OperatorPlugin {
-    library: PluginLibrary,
+    /// Shared with every operator built from this plugin, so the library stays mapped until
+    /// the last of them is gone.
+    library: Arc<PluginLibrary>,
+    vtable: OperatorVTable,
+    manifest: PluginManifest,
     name: String,
 }
 
 impl OperatorPlugin {
-    pub fn new(path: &Path, name: String) -> Result<Self, Error> {
-        let library = match PluginLibrary::new(path) {
-            Ok(v) => v,
-            Err(e) => {
-                debug!(
-                    "Failed to load library {}@[{}]: {}",
-                    name,
-                    path.display(),
-                    e
-                );
-                Err(e)?
-            }
-        };
-        Ok(Self { library, name })
+    /// Loads the plugin at `path`, registered as `name` or, if `None`, its manifest id. The
+    /// manifest is validated before the library is opened.
+    pub fn new(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, false)
+    }
+
+    /// Like [`Self::new`], but loads a private copy of the library so the file can be
+    /// replaced while the plugin is loaded.
+    pub fn new_copy(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        Self::open(path, name, true)
+    }
+
+    pub(crate) fn open(path: &Path, name: Option<String>, copy: bool) -> Result<Self, Error> {
+        let (manifest, name, library, vtable) = open_library::<OperatorVTable>(
+            path,
+            name,
+            copy,
+            PluginKind::Operator,
+            OPERATOR_VTABLE_SYMBOL,
+        )?;
+        Ok(Self {
+            library: Arc::new(library),
+            vtable,
+            manifest,
+            name,
+        })
     }
 
     pub fn build(&self, id: Option<String>) -> Result<Box<dyn Operator>, Error> {
-        let constructor: libloading::Symbol<fn(Option<String>) -> Box<dyn Operator>> =
-            match self.library.load_symbol(b"new") {
-                Ok(v) => v,
-                Err(e) => {
-                    debug!(
-                        "Failed to load symbol 'new' in {}@[{}]",
-                        self.name,
-                        self.library.path().display()
-                    );
-                    Err(e)?
-                }
-            };
-        Ok(constructor(id))
+        let operator =
+            unsafe { FfiOperator::new(self.library.clone(), self.vtable, id.as_deref()) }?;
+        Ok(Box::new(operator))
     }
 }
 
@@ -51,6 +60,29 @@ impl Plugin for OperatorPlugin {
         &self.name
     }
 
+    fn path(&self) -> &Path {
+        self.library.path()
+    }
+
+    fn kind(&self) -> PluginKind {
+        PluginKind::Operator
+    }
+
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    fn modified(&self) -> Option<std::time::SystemTime> {
+        self.library.modified()
+    }
+
+    fn reload(&self) -> Result<Box<dyn Plugin>, Error> {
+        Ok(Box::new(Self::new_copy(
+            self.library.path(),
+            Some(self.name.clone()),
+        )?))
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
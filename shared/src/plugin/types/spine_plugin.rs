@@ -0,0 +1,138 @@
+use crate::{
+    operator::Operator,
+    plugin::{Error, Plugin, PluginKind, abi::PluginDeclaration, manifest::PluginManifest},
+    spine::{DEFAULT_SKIN, SpineAssets, SpineOperator, SpineSkin},
+};
+use std::path::Path;
+use tracing::debug;
+
+/// Operators built by the host from a Spine atlas and the `.skel` or `.json` skeleton next to
+/// it, plus the skins in the skins directory next to the atlas. A manifest is optional;
+/// without one the plugin is named after the atlas and lists the skins found on disk and the
+/// animations found in the skeleton.
+#[derive(Debug)]
+pub struct SpinePlugin {
+    assets: SpineAssets,
+    manifest: PluginManifest,
+    name: String,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl SpinePlugin {
+    /// Reads the assets of the atlas at `path` once, so broken files are reported on load
+    /// rather than on spawn.
+    pub fn open(path: &Path, name: Option<String>) -> Result<Self, Error> {
+        let assets = assets_for(path)?;
+        let skin = SpineSkin::load("", "default", &assets.atlas, &assets.skeleton_file()).map_err(
+            |e| {
+                debug!("Failed to read Spine assets {:?}: {}", assets, e);
+                Error::InvalidAssets(format!("{}: {}", path.display(), e))
+            },
+        )?;
+        let mut manifest = read_manifest(path)?;
+        if manifest.skins.is_empty() {
+            manifest.skins = std::iter::once(DEFAULT_SKIN.to_owned())
+                .chain(assets.skins().into_keys().filter(|id| id != DEFAULT_SKIN))
+                .collect();
+        }
+        if manifest.animations.is_empty() {
+            manifest.animations = skin.animations().to_vec();
+        }
+        let name = name.unwrap_or_else(|| manifest.id.clone());
+        Ok(Self {
+            modified: assets.modified(),
+            assets,
+            manifest,
+            name,
+        })
+    }
+
+    pub fn assets(&self) -> &SpineAssets {
+        &self.assets
+    }
+
+    pub fn build(&self, id: Option<String>) -> Result<Box<dyn Operator>, Error> {
+        let id = id.unwrap_or_else(|| self.name.clone());
+        let operator = SpineOperator::load(&id, &self.assets).map_err(|e| {
+            debug!("Failed to build Spine operator {}: {}", id, e);
+            Error::InvalidAssets(format!("{}: {}", self.assets.atlas.display(), e))
+        })?;
+        Ok(Box::new(operator))
+    }
+}
+
+impl Plugin for SpinePlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &Path {
+        &self.assets.atlas
+    }
+
+    fn kind(&self) -> PluginKind {
+        PluginKind::Spine
+    }
+
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    fn modified(&self) -> Option<std::time::SystemTime> {
+        self.modified
+    }
+
+    fn reload(&self) -> Result<Box<dyn Plugin>, Error> {
+        Ok(Box::new(Self::open(
+            &self.assets.atlas,
+            Some(self.name.clone()),
+        )?))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+fn assets_for(atlas: &Path) -> Result<SpineAssets, Error> {
+    SpineAssets::for_atlas(atlas).ok_or_else(|| {
+        debug!("No skeleton next to {}", atlas.display());
+        Error::InvalidAssets(format!(
+            "{} has no .skel or .json skeleton next to it",
+            atlas.display()
+        ))
+    })
+}
+
+/// The manifest next to `atlas`, or one named after the atlas if there is none.
+pub(crate) fn read_manifest(atlas: &Path) -> Result<PluginManifest, Error> {
+    if PluginManifest::path_for(atlas).is_file() {
+        return PluginManifest::load(atlas, PluginKind::Spine);
+    }
+    let id = atlas
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let manifest = PluginManifest {
+        name: id.clone(),
+        id,
+        version: "0.0.0".to_owned(),
+        author: None,
+        kind: PluginKind::Spine,
+        skins: Vec::new(),
+        animations: Vec::new(),
+        custom_events: Vec::new(),
+        api_version: format!(
+            "{}.{}",
+            PluginDeclaration::CURRENT.api_major,
+            PluginDeclaration::CURRENT.api_minor
+        ),
+    };
+    manifest.validate(PluginKind::Spine)?;
+    Ok(manifest)
+}
+
+/// Modification time of the assets of `atlas`, as [`Plugin::modified`] reports it.
+pub(crate) fn modified(atlas: &Path) -> Option<std::time::SystemTime> {
+    SpineAssets::for_atlas(atlas)?.modified()
+}
@@ -0,0 +1,281 @@
+use crate::events::Event;
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeDelta, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// When a scheduled event fires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Schedule {
+    /// Once, `seconds` from now.
+    Delay { seconds: f64 },
+    /// Once, at an absolute point in time.
+    At { time: DateTime<Local> },
+    /// Every `seconds`, the first time `seconds` from now.
+    Every { seconds: f64 },
+    /// Every day at the given local time, optionally restricted to some weekdays.
+    Daily {
+        at: NaiveTime,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weekdays: Option<Vec<Weekday>>,
+    },
+}
+
+impl Schedule {
+    /// First time the schedule fires after `now`, or `None` if it never will, including when
+    /// that time is beyond what a date can represent.
+    fn first(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Schedule::Delay { seconds } | Schedule::Every { seconds } => {
+                now.checked_add_signed(seconds_delta(*seconds)?)
+            }
+            Schedule::At { time } => (*time > now).then_some(*time),
+            Schedule::Daily { .. } => self.next(now, now),
+        }
+    }
+
+    /// Time the schedule fires next after having fired at `last`, or `None` if it is done.
+    fn next<Tz: TimeZone>(&self, last: DateTime<Tz>, now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match self {
+            Schedule::Delay { .. } | Schedule::At { .. } => None,
+            Schedule::Every { seconds } => {
+                let interval = seconds_delta(*seconds)?;
+                let next = last.checked_add_signed(interval)?;
+                if next > now {
+                    return Some(next);
+                }
+                // Skip runs missed while the host was busy instead of firing them in a burst.
+                let interval_ms = interval.num_milliseconds();
+                let missed = (now.clone() - next.clone()).num_milliseconds() / interval_ms + 1;
+                next.checked_add_signed(TimeDelta::try_milliseconds(
+                    missed.checked_mul(interval_ms)?,
+                )?)
+            }
+            Schedule::Daily { at, weekdays } => (0..=7).find_map(|offset| {
+                let date = now.date_naive().checked_add_days(Days::new(offset))?;
+                if weekdays
+                    .as_ref()
+                    .is_some_and(|days| !days.contains(&date.weekday()))
+                {
+                    return None;
+                }
+                // A time in a DST gap does not exist that day; the schedule skips it.
+                now.timezone()
+                    .from_local_datetime(&date.and_time(*at))
+                    .earliest()
+                    .filter(|time| *time > now)
+            }),
+        }
+    }
+}
+
+fn seconds_delta(seconds: f64) -> Option<TimeDelta> {
+    (seconds.is_finite() && seconds > 0.0)
+        .then(|| TimeDelta::try_milliseconds((seconds * 1000.0) as i64))
+        .flatten()
+        .filter(|delta| !delta.is_zero())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEvent {
+    pub id: u64,
+    pub event: Event,
+    pub schedule: Schedule,
+    pub next_fire: DateTime<Local>,
+}
+
+/// Events waiting to be sent to their operators.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    next_id: u64,
+    entries: BTreeMap<u64, ScheduledEvent>,
+}
+
+impl Scheduler {
+    /// Registers an event; fails if the schedule would never fire.
+    pub fn schedule(
+        &mut self,
+        event: Event,
+        schedule: Schedule,
+        now: DateTime<Local>,
+    ) -> Result<&ScheduledEvent, String> {
+        let next_fire = schedule
+            .first(now)
+            .ok_or_else(|| format!("schedule {:?} never fires", schedule))?;
+        self.next_id += 1;
+        Ok(self.entries.entry(self.next_id).or_insert(ScheduledEvent {
+            id: self.next_id,
+            event,
+            schedule,
+            next_fire,
+        }))
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Option<ScheduledEvent> {
+        self.entries.remove(&id)
+    }
+
+    pub fn list(&self) -> Vec<ScheduledEvent> {
+        self.entries.values().cloned().collect()
+    }
+
+    /// Takes all events due at `now`. Recurring entries are rescheduled, finished ones removed.
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Vec<Event> {
+        let mut due = Vec::new();
+        self.entries.retain(|_, entry| {
+            if entry.next_fire > now {
+                return true;
+            }
+            due.push(entry.event.clone());
+            match entry.schedule.next(entry.next_fire, now) {
+                Some(next_fire) => {
+                    entry.next_fire = next_fire;
+                    true
+                }
+                None => false,
+            }
+        });
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{Europe::Berlin, Tz};
+
+    fn berlin(date: (i32, u32, u32), time: (u32, u32, u32)) -> DateTime<Tz> {
+        Berlin
+            .with_ymd_and_hms(date.0, date.1, date.2, time.0, time.1, time.2)
+            .single()
+            .unwrap()
+    }
+
+    fn daily(at: (u32, u32), weekdays: Option<Vec<Weekday>>) -> Schedule {
+        Schedule::Daily {
+            at: NaiveTime::from_hms_opt(at.0, at.1, 0).unwrap(),
+            weekdays,
+        }
+    }
+
+    #[test]
+    fn every_fires_one_interval_after_the_last_run() {
+        let last = berlin((2025, 3, 3), (10, 0, 0));
+        let now = berlin((2025, 3, 3), (10, 0, 1));
+        let next = Schedule::Every { seconds: 5.0 }.next(last, now);
+        assert_eq!(next, Some(berlin((2025, 3, 3), (10, 0, 5))));
+    }
+
+    #[test]
+    fn every_skips_missed_runs() {
+        let last = berlin((2025, 3, 3), (10, 0, 0));
+        let now = berlin((2025, 3, 3), (10, 0, 10)) + TimeDelta::milliseconds(500);
+        let next = Schedule::Every { seconds: 1.0 }.next(last, now);
+        assert_eq!(next, Some(berlin((2025, 3, 3), (10, 0, 11))));
+    }
+
+    #[test]
+    fn every_fires_after_now_when_exactly_on_a_missed_run() {
+        let last = berlin((2025, 3, 3), (10, 0, 0));
+        let now = berlin((2025, 3, 3), (10, 0, 3));
+        let next = Schedule::Every { seconds: 1.0 }.next(last, now);
+        assert_eq!(next, Some(berlin((2025, 3, 3), (10, 0, 4))));
+    }
+
+    #[test]
+    fn every_catches_up_a_long_suspend_on_the_interval_grid() {
+        let last = berlin((2025, 3, 3), (10, 0, 0));
+        let now = last + TimeDelta::days(30) + TimeDelta::microseconds(1500);
+        let next = Schedule::Every { seconds: 0.001 }.next(last, now).unwrap();
+        assert_eq!(
+            next,
+            last + TimeDelta::days(30) + TimeDelta::milliseconds(2)
+        );
+    }
+
+    #[test]
+    fn daily_fires_later_today_or_tomorrow() {
+        let now = berlin((2025, 3, 3), (10, 0, 0));
+        assert_eq!(
+            daily((11, 0), None).next(now, now),
+            Some(berlin((2025, 3, 3), (11, 0, 0)))
+        );
+        assert_eq!(
+            daily((9, 0), None).next(now, now),
+            Some(berlin((2025, 3, 4), (9, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn daily_only_fires_on_its_weekdays() {
+        // 2025-03-03 is a Monday.
+        let now = berlin((2025, 3, 3), (10, 0, 0));
+        let schedule = daily((9, 0), Some(vec![Weekday::Fri, Weekday::Mon]));
+        assert_eq!(
+            schedule.next(now, now),
+            Some(berlin((2025, 3, 7), (9, 0, 0)))
+        );
+        let schedule = daily((11, 0), Some(vec![Weekday::Mon]));
+        assert_eq!(
+            schedule.next(now, now),
+            Some(berlin((2025, 3, 3), (11, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn daily_with_no_weekdays_never_fires() {
+        let now = berlin((2025, 3, 3), (10, 0, 0));
+        assert_eq!(daily((9, 0), Some(Vec::new())).next(now, now), None);
+    }
+
+    #[test]
+    fn daily_skips_a_day_whose_time_falls_in_a_dst_gap() {
+        // Clocks jump from 02:00 to 03:00 on 2025-03-30 in Berlin.
+        let now = berlin((2025, 3, 29), (12, 0, 0));
+        assert_eq!(
+            daily((2, 30), None).next(now, now),
+            Some(berlin((2025, 3, 31), (2, 30, 0)))
+        );
+    }
+
+    #[test]
+    fn daily_fires_once_on_the_earlier_of_repeated_times() {
+        // Clocks fall back from 03:00 to 02:00 on 2025-10-26 in Berlin.
+        let now = berlin((2025, 10, 25), (12, 0, 0));
+        let first = daily((2, 30), None).next(now, now).unwrap();
+        assert_eq!(
+            first,
+            Berlin
+                .with_ymd_and_hms(2025, 10, 26, 2, 30, 0)
+                .earliest()
+                .unwrap()
+        );
+        assert_eq!(
+            daily((2, 30), None).next(first, first),
+            Some(berlin((2025, 10, 27), (2, 30, 0)))
+        );
+    }
+
+    #[test]
+    fn rejects_a_delay_beyond_the_representable_range() {
+        let mut scheduler = Scheduler::default();
+        for seconds in [1e15, 1e300] {
+            let scheduled = scheduler.schedule(
+                Event::Retreat {
+                    op_id: "op".to_owned(),
+                },
+                Schedule::Delay { seconds },
+                Local::now(),
+            );
+            assert!(scheduled.is_err(), "delay of {} seconds", seconds);
+        }
+        assert!(scheduler.list().is_empty());
+    }
+
+    #[test]
+    fn every_ends_when_the_next_run_is_beyond_the_representable_range() {
+        let last = berlin((2025, 3, 3), (10, 0, 0));
+        let now = berlin((2025, 3, 3), (10, 0, 1));
+        assert_eq!(Schedule::Every { seconds: 1e15 }.next(last, now), None);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    behaviour::BehaviourConfig,
+    ipc::command_context::CommandContext,
+    operator::{OperatorInstance, OperatorRegistry},
+    plugin::PluginRegistry,
+    transform::Transform,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf};
+
+pub const SESSION_DIR: &str = "./sessions/";
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Snapshot of the loaded plugins and spawned operators, persisted as RON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub plugins: Vec<PluginEntry>,
+    pub operators: Vec<OperatorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginEntry {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperatorEntry {
+    pub id: String,
+    pub plugin: String,
+    #[serde(default)]
+    pub skin: Option<String>,
+    #[serde(default)]
+    pub animation: Option<String>,
+    #[serde(default)]
+    pub transform: Transform,
+    #[serde(default)]
+    pub behaviour: Option<BehaviourConfig>,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    InvalidName(String),
+    Io(std::io::Error),
+    Serialisation(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidName(e) => write!(f, "Invalid session name: {}", e),
+            Error::Io(e) => write!(f, "Session file error: {}", e),
+            Error::Serialisation(e) => write!(f, "Malformed session: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<&OperatorInstance> for OperatorEntry {
+    fn from(op: &OperatorInstance) -> Self {
+        let state = op.state();
+        Self {
+            id: state.id.clone(),
+            plugin: state.plugin.clone(),
+            skin: state.skin.clone(),
+            animation: state.animation.clone(),
+            transform: state.transform,
+            behaviour: op.behaviour().map(|b| b.config().clone()),
+        }
+    }
+}
+
+impl Session {
+    pub fn capture(plugins: &PluginRegistry, operators: &OperatorRegistry) -> Self {
+        let plugins = plugins
+            .plugin_infos()
+            .into_iter()
+            .map(|info| PluginEntry {
+                name: info.name,
+                path: info.path,
+            })
+            .collect();
+        let mut operators: Vec<OperatorEntry> =
+            operators.values().map(OperatorEntry::from).collect();
+        operators.sort_by(|a, b| a.id.cmp(&b.id));
+        Self { plugins, operators }
+    }
+
+    /// Location of the session file of the given profile.
+    pub fn path(name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::InvalidName(format!(
+                "{:?} may only contain letters, digits, '-' and '_'",
+                name
+            )));
+        }
+        Ok(PathBuf::from(SESSION_DIR).join(format!("{}.ron", name)))
+    }
+
+    pub fn to_ron(&self) -> Result<String, Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| Error::Serialisation(e.to_string()))
+    }
+
+    pub fn load(name: &str) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(Self::path(name)?)?;
+        ron::from_str(&content).map_err(|e| Error::Serialisation(e.to_string()))
+    }
+
+    pub fn save(&self, name: &str) -> Result<PathBuf, Error> {
+        let path = Self::path(name)?;
+        std::fs::create_dir_all(SESSION_DIR)?;
+        std::fs::write(&path, self.to_ron()?)?;
+        Ok(path)
+    }
+
+    /// Loads missing plugins and spawns the recorded operators. Returns a message for every
+    /// plugin or operator that could not be restored.
+    pub fn restore(&self, ctx: &mut CommandContext) -> Vec<String> {
+        crate::ipc::commands::restore_session(self, ctx)
+    }
+}
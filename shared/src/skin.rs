@@ -1,14 +1,17 @@
 use eframe::egui;
 use rusty_spine::controller::SkeletonController;
+use std::collections::{BTreeMap, HashMap};
 
 pub trait OperatorSkin: std::fmt::Debug {
+    /// Builds the skin from its atlas and skeleton. Fails with a message if they cannot be
+    /// read.
     fn new(
         operator_id: &str,
         skin_id: &str,
         atlas_path: String,
         skeleton_file: SkeletonFile,
         texture: crate::texture::SpineTexture,
-    ) -> Self
+    ) -> Result<Self, String>
     where
         Self: Sized;
     fn operator_id(&self) -> String;
@@ -17,7 +20,112 @@ pub trait OperatorSkin: std::fmt::Debug {
     fn ensure_textures_loaded(&mut self, ctx: &egui::Context);
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum SkeletonFile {
     Binary(String),
     Json(String),
 }
+
+/// The files a skin is built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinSource {
+    pub atlas: String,
+    pub skeleton: SkeletonFile,
+}
+
+/// The skins one operator can switch between, each with its own atlas and skeleton. Skins
+/// other than the current one are loaded the first time they are needed.
+#[derive(Debug)]
+pub struct SkinSet<S: OperatorSkin> {
+    operator_id: String,
+    sources: BTreeMap<String, SkinSource>,
+    loaded: HashMap<String, S>,
+    current: String,
+}
+
+impl<S: OperatorSkin> SkinSet<S> {
+    /// A set wearing `current`, which can switch to any skin in `sources`.
+    pub fn new(current: S, sources: BTreeMap<String, SkinSource>) -> Self {
+        Self {
+            operator_id: current.operator_id(),
+            current: current.id(),
+            loaded: HashMap::from([(current.id(), current)]),
+            sources,
+        }
+    }
+
+    pub fn operator_id(&self) -> &str {
+        &self.operator_id
+    }
+
+    /// Ids of all skins in the set, sorted.
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self
+            .sources
+            .keys()
+            .chain(
+                self.loaded
+                    .keys()
+                    .filter(|id| !self.sources.contains_key(*id)),
+            )
+            .map(String::as_str)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.loaded.contains_key(id) || self.sources.contains_key(id)
+    }
+
+    pub fn current_id(&self) -> &str {
+        &self.current
+    }
+
+    pub fn current(&self) -> &S {
+        &self.loaded[&self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut S {
+        self.loaded.get_mut(&self.current).unwrap()
+    }
+
+    /// The skin `id`, built with `load` from its source if it is not loaded yet. Fails with
+    /// the list of valid skins if the set has no skin `id`.
+    pub fn load(
+        &mut self,
+        id: &str,
+        load: impl FnOnce(&str, &SkinSource) -> Result<S, String>,
+    ) -> Result<&mut S, String> {
+        if !self.loaded.contains_key(id) {
+            let Some(source) = self.sources.get(id) else {
+                return Err(self.unknown(id));
+            };
+            let skin = load(id, source)?;
+            self.loaded.insert(id.to_owned(), skin);
+        }
+        Ok(self.loaded.get_mut(id).unwrap())
+    }
+
+    /// Makes the skin `id` the current one. It must have been loaded with [`Self::load`].
+    pub fn switch(&mut self, id: &str) -> Result<(), String> {
+        if !self.loaded.contains_key(id) {
+            return Err(if self.sources.contains_key(id) {
+                format!("skin {} of operator {} is not loaded", id, self.operator_id)
+            } else {
+                self.unknown(id)
+            });
+        }
+        self.current = id.to_owned();
+        Ok(())
+    }
+
+    fn unknown(&self, id: &str) -> String {
+        format!(
+            "operator {} has no skin {}, expected one of {}",
+            self.operator_id,
+            id,
+            self.ids().join(", ")
+        )
+    }
+}
@@ -0,0 +1,480 @@
+use crate::{
+    events::{AnimationEvent, AnimationEventKind, AnimationOptions, Event, QueuedAnimation},
+    operator::Operator,
+    skin::{OperatorSkin, SkeletonFile, SkinSet, SkinSource},
+    texture::{self, SpineTexture},
+};
+use eframe::egui::{self, Color32, Mesh, Shape, epaint::Vertex, pos2};
+use rusty_spine::{
+    AnimationStateData, Atlas, SkeletonBinary, SkeletonJson, SpineError,
+    controller::SkeletonController,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+    time::SystemTime,
+};
+use tracing::debug;
+
+pub const ATLAS_EXTENSION: &str = "atlas";
+
+/// Skeleton file extensions, looked for in this order next to an atlas.
+pub const SKELETON_EXTENSIONS: [&str; 2] = ["skel", "json"];
+
+/// Extension of the directory next to an atlas that holds its other skins, one atlas and
+/// skeleton per skin: `hero.atlas` has its skins in `hero.skins/winter.atlas`, ...
+pub const SKINS_EXTENSION: &str = "skins";
+
+/// Skin an operator wears when spawned, built from the atlas it was loaded from.
+pub const DEFAULT_SKIN: &str = "default";
+
+static TEXTURE_CALLBACKS: Once = Once::new();
+
+pub fn is_atlas(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == ATLAS_EXTENSION)
+}
+
+pub fn is_skins_dir(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == SKINS_EXTENSION)
+}
+
+/// The files a Spine skeleton is built from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpineAssets {
+    pub atlas: PathBuf,
+    /// Read as binary if it has a `.skel` extension, as JSON otherwise.
+    pub skeleton: PathBuf,
+}
+
+impl SpineAssets {
+    /// The assets of `atlas`, whose skeleton is the `.skel` or `.json` file next to it with the
+    /// same stem.
+    pub fn for_atlas(atlas: &Path) -> Option<Self> {
+        SKELETON_EXTENSIONS
+            .iter()
+            .map(|ext| atlas.with_extension(ext))
+            .find(|skeleton| skeleton.is_file())
+            .map(|skeleton| Self {
+                atlas: atlas.to_path_buf(),
+                skeleton,
+            })
+    }
+
+    pub fn source(&self) -> SkinSource {
+        SkinSource {
+            atlas: self.atlas.to_string_lossy().into_owned(),
+            skeleton: self.skeleton_file(),
+        }
+    }
+
+    /// The skins in the skins directory next to the atlas, keyed by their atlas stem.
+    pub fn skins(&self) -> BTreeMap<String, SpineAssets> {
+        let Ok(entries) = std::fs::read_dir(self.atlas.with_extension(SKINS_EXTENSION)) else {
+            return BTreeMap::new();
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_atlas(path))
+            .filter_map(|path| {
+                let id = path.file_stem()?.to_string_lossy().into_owned();
+                Some((id, Self::for_atlas(&path)?))
+            })
+            .collect()
+    }
+
+    pub fn skeleton_file(&self) -> SkeletonFile {
+        let path = self.skeleton.to_string_lossy().into_owned();
+        if self.skeleton.extension().is_some_and(|ext| ext == "skel") {
+            SkeletonFile::Binary(path)
+        } else {
+            SkeletonFile::Json(path)
+        }
+    }
+
+    /// Latest modification time of the atlas, the skeleton and the skins.
+    pub fn modified(&self) -> Option<SystemTime> {
+        let skins = self.skins();
+        [&self.atlas, &self.skeleton]
+            .into_iter()
+            .chain(
+                skins
+                    .values()
+                    .flat_map(|skin| [&skin.atlas, &skin.skeleton]),
+            )
+            .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .max()
+    }
+}
+
+/// A skeleton loaded from an atlas and a skeleton file, with its textures.
+pub struct SpineSkin {
+    operator_id: String,
+    id: String,
+    atlas: Arc<Atlas>,
+    controller: SkeletonController,
+    animations: Vec<String>,
+    /// Filled by the animation state listener, drained by the host every frame.
+    events: Arc<Mutex<Vec<AnimationEvent>>>,
+}
+
+impl fmt::Debug for SpineSkin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpineSkin")
+            .field("operator_id", &self.operator_id)
+            .field("id", &self.id)
+            .field("animations", &self.animations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SpineSkin {
+    /// Reads the atlas and skeleton. Textures are only decoded once the skin is rendered.
+    pub fn load(
+        operator_id: &str,
+        skin_id: &str,
+        atlas_path: &Path,
+        skeleton_file: &SkeletonFile,
+    ) -> Result<Self, SpineError> {
+        TEXTURE_CALLBACKS.call_once(texture::set_texture_cb);
+        let atlas = Arc::new(Atlas::new_from_file(atlas_path)?);
+        let skeleton_data = Arc::new(match skeleton_file {
+            SkeletonFile::Binary(path) => {
+                SkeletonBinary::new(atlas.clone()).read_skeleton_data_file(path)?
+            }
+            SkeletonFile::Json(path) => {
+                SkeletonJson::new(atlas.clone()).read_skeleton_data_file(path)?
+            }
+        });
+        let animations = skeleton_data
+            .animations()
+            .map(|animation| animation.name().to_owned())
+            .collect();
+        let animation_state_data = Arc::new(AnimationStateData::new(skeleton_data.clone()));
+        let mut controller = SkeletonController::new(skeleton_data, animation_state_data);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        controller.animation_state.set_listener(move |_, event| {
+            if let Some(event) = animation_event(event) {
+                sink.lock().unwrap().push(event);
+            }
+        });
+        Ok(Self {
+            operator_id: operator_id.to_owned(),
+            id: skin_id.to_owned(),
+            atlas,
+            controller,
+            animations,
+            events,
+        })
+    }
+
+    /// Names of the animations in the skeleton.
+    pub fn animations(&self) -> &[String] {
+        &self.animations
+    }
+
+    pub fn has_animation(&self, animation: &str) -> bool {
+        self.animations.iter().any(|a| a == animation)
+    }
+
+    /// Animation events emitted since the last call, oldest first.
+    pub fn take_events(&self) -> Vec<AnimationEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+
+    /// Plays `animation` on the track of `options`, replacing what plays there unless
+    /// `queued`. A `loop_count` queues the animation that many times.
+    pub fn play(
+        &mut self,
+        animation: &str,
+        options: &AnimationOptions,
+        queued: bool,
+    ) -> Result<(), SpineError> {
+        let state = &mut self.controller.animation_state;
+        let looping = options.loop_count.is_none() && options.looping;
+        for play in 0..options.loop_count.unwrap_or(1) {
+            let mut entry = if play == 0 && !queued {
+                state.set_animation_by_name(options.track, animation, looping)?
+            } else {
+                state.add_animation_by_name(options.track, animation, looping, 0.0)?
+            };
+            // Repeats follow each other without a crossfade.
+            match options.mix {
+                _ if play > 0 => entry.set_mix_duration(0.0),
+                Some(mix) => entry.set_mix_duration(mix),
+                None => {}
+            }
+            entry.set_time_scale(options.speed);
+        }
+        Ok(())
+    }
+
+    /// Paints the skeleton in its current pose, its root at the origin.
+    pub fn render(&mut self, ui: &mut egui::Ui) {
+        let painter = ui.painter();
+        for renderable in self.controller.renderables() {
+            // The renderer object of an attachment is the `SpineTexture` of its atlas page.
+            let Some(texture) = renderable
+                .attachment_renderer_object
+                .and_then(|object| unsafe { &*(object as *const SpineTexture) }.texture_id())
+            else {
+                continue;
+            };
+            let [r, g, b, a] = [
+                renderable.color.r,
+                renderable.color.g,
+                renderable.color.b,
+                renderable.color.a,
+            ]
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8);
+            let color = if renderable.premultiplied_alpha {
+                Color32::from_rgba_premultiplied(r, g, b, a)
+            } else {
+                Color32::from_rgba_unmultiplied(r, g, b, a)
+            };
+            let mut mesh = Mesh::with_texture(texture);
+            mesh.vertices = renderable
+                .vertices
+                .iter()
+                .zip(&renderable.uvs)
+                .map(|(pos, uv)| Vertex {
+                    // Spine's y axis points up.
+                    pos: pos2(pos[0], -pos[1]),
+                    uv: pos2(uv[0], uv[1]),
+                    color,
+                })
+                .collect();
+            mesh.indices = renderable.indices.iter().map(|&i| u32::from(i)).collect();
+            painter.add(Shape::mesh(mesh));
+        }
+    }
+}
+
+impl OperatorSkin for SpineSkin {
+    fn new(
+        operator_id: &str,
+        skin_id: &str,
+        atlas_path: String,
+        skeleton_file: SkeletonFile,
+        _texture: SpineTexture,
+    ) -> Result<Self, String> {
+        Self::load(operator_id, skin_id, Path::new(&atlas_path), &skeleton_file)
+            .map_err(|e| format!("Cannot load skin {} from {}: {}", skin_id, atlas_path, e))
+    }
+
+    fn operator_id(&self) -> String {
+        self.operator_id.clone()
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn controller_mut(&mut self) -> &mut SkeletonController {
+        &mut self.controller
+    }
+
+    fn ensure_textures_loaded(&mut self, ctx: &egui::Context) {
+        for page in self.atlas.pages() {
+            let mut object = page.renderer_object();
+            if let Some(texture) = unsafe { object.get::<SpineTexture>() } {
+                texture.ensure_loaded(ctx);
+            }
+        }
+    }
+}
+
+/// The host's view of an event reported by the Spine runtime. Disposal of track entries is
+/// an implementation detail and not reported.
+fn animation_event(event: rusty_spine::AnimationEvent) -> Option<AnimationEvent> {
+    use rusty_spine::AnimationEvent as Spine;
+    let (track_entry, kind) = match event {
+        Spine::Start { track_entry } => (track_entry, AnimationEventKind::Start),
+        Spine::Interrupt { track_entry } => (track_entry, AnimationEventKind::Interrupt),
+        Spine::End { track_entry } => (track_entry, AnimationEventKind::End),
+        Spine::Complete { track_entry } => (track_entry, AnimationEventKind::Complete),
+        Spine::Event {
+            track_entry,
+            name,
+            int,
+            float,
+            string,
+            ..
+        } => (
+            track_entry,
+            AnimationEventKind::Event {
+                name: name.to_owned(),
+                int,
+                float,
+                string: string.to_owned(),
+            },
+        ),
+        _ => return None,
+    };
+    Some(AnimationEvent {
+        track: track_entry.track_index(),
+        animation: track_entry.animation().name().to_owned(),
+        kind,
+    })
+}
+
+/// An operator the host builds from Spine assets, for artists who have no plugin to compile.
+#[derive(Debug)]
+pub struct SpineOperator {
+    id: String,
+    skins: SkinSet<SpineSkin>,
+    /// Animation playing on track 0, carried over when the skin changes.
+    animation: Option<String>,
+    /// Context of the last frame, so a skin switched to by an event can upload its textures
+    /// before it is first drawn.
+    ctx: Option<egui::Context>,
+}
+
+// The skeleton holds raw pointers into the Spine runtime. It is only reached through
+// `&mut self`, which the operator registry lock serialises.
+unsafe impl Send for SpineOperator {}
+unsafe impl Sync for SpineOperator {}
+
+impl SpineOperator {
+    /// Builds the operator wearing the skin of `assets`, able to switch to the skins next to
+    /// it.
+    pub fn load(id: &str, assets: &SpineAssets) -> Result<Self, SpineError> {
+        let skin = SpineSkin::load(id, DEFAULT_SKIN, &assets.atlas, &assets.skeleton_file())?;
+        let sources = assets
+            .skins()
+            .into_iter()
+            .map(|(skin, assets)| (skin, assets.source()))
+            .collect();
+        Ok(Self {
+            id: id.to_owned(),
+            skins: SkinSet::new(skin, sources),
+            animation: None,
+            ctx: None,
+        })
+    }
+
+    /// Plays `animation` followed by `then`. Nothing is played unless the skin has all of
+    /// them.
+    fn set_animation(
+        &mut self,
+        animation: &str,
+        options: &AnimationOptions,
+        then: &[QueuedAnimation],
+    ) -> Result<(), String> {
+        let skin = self.skins.current_mut();
+        let names = std::iter::once(animation).chain(then.iter().map(|q| q.ani.as_str()));
+        for name in names {
+            if !skin.has_animation(name) {
+                return Err(format!(
+                    "unknown animation {}, expected one of {}",
+                    name,
+                    skin.animations().join(", ")
+                ));
+            }
+        }
+        std::iter::once(options)
+            .chain(then.iter().map(|q| &q.options))
+            .try_for_each(AnimationOptions::validate)?;
+        skin.play(animation, options, options.queue)
+            .and_then(|()| {
+                then.iter()
+                    .try_for_each(|q| skin.play(&q.ani, &q.options, true))
+            })
+            .map_err(|e| e.to_string())?;
+        if options.track == 0 && !options.queue {
+            self.animation = Some(animation.to_owned());
+        }
+        Ok(())
+    }
+
+    /// Switches to the skin `id`, which continues the current animation where the previous
+    /// skin left off.
+    fn set_skin(&mut self, id: &str) -> Result<(), String> {
+        if id == self.skins.current_id() {
+            return Ok(());
+        }
+        let track_time = self
+            .skins
+            .current_mut()
+            .controller_mut()
+            .animation_state
+            .get_current(0)
+            .map(|entry| entry.track_time());
+        let operator_id = &self.id;
+        let skin = self.skins.load(id, |id, source| {
+            SpineSkin::load(operator_id, id, Path::new(&source.atlas), &source.skeleton)
+                .map_err(|e| format!("cannot load skin {} of operator {}: {}", id, operator_id, e))
+        })?;
+        if let Some(ctx) = &self.ctx {
+            skin.ensure_textures_loaded(ctx);
+        }
+        if let Some(animation) = self.animation.as_deref().filter(|a| skin.has_animation(a)) {
+            let controller = skin.controller_mut();
+            match controller
+                .animation_state
+                .set_animation_by_name(0, animation, true)
+            {
+                Ok(mut entry) => entry.set_track_time(track_time.unwrap_or_default()),
+                Err(e) => debug!("Skin {} cannot play {}: {}", id, animation, e),
+            }
+        }
+        // Poses the skeleton, so the first frame in the new skin is not drawn in setup pose.
+        skin.controller_mut().update(0.0);
+        self.skins.switch(id)
+    }
+
+    fn remember_context(&mut self, ctx: &egui::Context) {
+        if self.ctx.is_none() {
+            self.ctx = Some(ctx.clone());
+        }
+    }
+}
+
+impl Operator for SpineOperator {
+    fn render(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        self.remember_context(ctx);
+        let skin = self.skins.current_mut();
+        skin.ensure_textures_loaded(ctx);
+        skin.render(ui);
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    /// Animations the skeleton lacks are skipped, so the host's walk, sit and sleep
+    /// animations are optional.
+    fn start_animation(&mut self, anim: &str) {
+        if let Err(e) = self.set_animation(anim, &AnimationOptions::default(), &[]) {
+            debug!("Operator {} keeps its animation: {}", self.id, e);
+        }
+    }
+
+    fn update_animation(&mut self, ctx: &egui::Context) {
+        let dt = ctx.input(|i| i.stable_dt);
+        self.skins.current_mut().controller_mut().update(dt);
+    }
+
+    fn take_animation_events(&mut self) -> Vec<AnimationEvent> {
+        self.skins.current().take_events()
+    }
+
+    fn load_textures(&mut self, ctx: &egui::Context) {
+        self.remember_context(ctx);
+        self.skins.current_mut().ensure_textures_loaded(ctx);
+    }
+
+    fn event_handler(&mut self, event: Event) -> Result<(), String> {
+        match event {
+            Event::SetAnimation {
+                ani, options, then, ..
+            } => self.set_animation(&ani, &options, &then),
+            Event::SetSkin { skin, .. } => self.set_skin(&skin),
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,41 +1,220 @@
 use eframe::egui::{self, TextureFilter, TextureHandle, TextureWrapMode};
 use rusty_spine::atlas::{AtlasFilter, AtlasFormat, AtlasWrap};
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt::Debug,
+    sync::{LazyLock, Mutex},
+};
 use tracing::{debug, trace, warn};
 use wgpu::TextureFormat;
 
+/// An atlas page image together with how it is sampled. The same image sampled differently is
+/// a different texture.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextureKey {
+    pub path: String,
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    pub x_wrap: TextureWrapMode,
+    pub y_wrap: TextureWrapMode,
+    pub format: TextureFormat,
+}
+
+#[derive(Debug)]
 pub enum SpineTexture {
-    Pending {
-        path: String,
-        min_filter: TextureFilter,
-        mag_filter: TextureFilter,
-        x_wrap: TextureWrapMode,
-        y_wrap: TextureWrapMode,
-        format: TextureFormat,
-    },
-    Loaded(TextureHandle),
+    Pending(TextureKey),
+    Loaded(CachedTexture),
+    /// The image at `path` could not be loaded; not retried.
+    Failed(String),
 }
 
-impl Debug for SpineTexture {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl SpineTexture {
+    /// Takes a pending texture from the [`TextureCache`], which decodes and uploads it unless
+    /// another atlas page uses it already. Does nothing if it is loaded or failed before.
+    pub fn ensure_loaded(&mut self, ctx: &egui::Context) {
+        let Self::Pending(key) = self else {
+            return;
+        };
+        *self = match texture_cache().acquire(key, ctx) {
+            Ok(texture) => Self::Loaded(texture),
+            Err(e) => {
+                warn!("Failed to load texture {}: {}", key.path, e);
+                Self::Failed(key.path.clone())
+            }
+        };
+    }
+
+    pub fn texture_id(&self) -> Option<egui::TextureId> {
         match self {
-            Self::Pending {
-                path,
-                min_filter,
-                mag_filter,
-                x_wrap,
-                y_wrap,
-                format,
-            } => f
-                .debug_struct("Pending")
-                .field("path", path)
-                .field("min_filter", min_filter)
-                .field("mag_filter", mag_filter)
-                .field("x_wrap", x_wrap)
-                .field("y_wrap", y_wrap)
-                .field("format", format)
-                .finish(),
-            Self::Loaded(handle) => f.debug_tuple("Loaded").field(&handle.name()).finish(), // NOTE: Semi useful
+            Self::Loaded(texture) => Some(texture.id()),
+            _ => None,
+        }
+    }
+}
+
+/// A texture handed out by the [`TextureCache`]. Dropping it releases its reference.
+pub struct CachedTexture {
+    key: TextureKey,
+    handle: TextureHandle,
+}
+
+impl CachedTexture {
+    pub fn id(&self) -> egui::TextureId {
+        self.handle.id()
+    }
+
+    pub fn key(&self) -> &TextureKey {
+        &self.key
+    }
+}
+
+impl Debug for CachedTexture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedTexture")
+            .field("key", &self.key)
+            .field("id", &self.handle.id())
+            .finish()
+    }
+}
+
+impl Drop for CachedTexture {
+    fn drop(&mut self) {
+        texture_cache().release(&self.key);
+    }
+}
+
+/// Counters of a [`TextureCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextureCacheStats {
+    /// Textures currently uploaded.
+    pub textures: usize,
+    /// Atlas pages currently using one of them.
+    pub references: usize,
+    /// Size of the uploaded images, at four bytes per pixel.
+    pub bytes: usize,
+    /// Requests served from the cache.
+    pub hits: u64,
+    /// Requests that decoded and uploaded an image.
+    pub misses: u64,
+    /// Textures freed after their last user went away.
+    pub evictions: u64,
+}
+
+struct CacheEntry {
+    handle: TextureHandle,
+    references: usize,
+    bytes: usize,
+}
+
+/// Uploaded textures shared by every atlas page showing the same image with the same sampler
+/// settings, so spawning an operator again does not decode and upload its images again. A
+/// texture is freed once no atlas page uses it anymore.
+///
+/// Sharing is limited to host-built Spine operators: the cache lives in the host's copy of
+/// this crate, and operator plugin libraries link their own copy, so textures they upload
+/// are neither shared with the host nor counted in its stats.
+#[derive(Default)]
+pub struct TextureCache {
+    entries: Mutex<HashMap<TextureKey, CacheEntry>>,
+    stats: Mutex<TextureCacheStats>,
+}
+
+impl Debug for TextureCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextureCache")
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The cache the textures of this copy of the crate go through; see [`TextureCache`] for
+/// what that covers in the host.
+pub fn texture_cache() -> &'static TextureCache {
+    static CACHE: LazyLock<TextureCache> = LazyLock::new(TextureCache::default);
+    &CACHE
+}
+
+impl TextureCache {
+    /// A reference to the texture for `key`, uploaded from its image on first use.
+    pub fn acquire(
+        &self,
+        key: &TextureKey,
+        ctx: &egui::Context,
+    ) -> Result<CachedTexture, image::ImageError> {
+        if let Some(texture) = self.reference(key) {
+            self.stats.lock().unwrap().hits += 1;
+            return Ok(texture);
+        }
+        // Decoded without holding the lock; if another page uploaded the same texture in the
+        // meantime, that one is used, this upload is dropped and the request counts as a hit.
+        let image = image::open(&key.path)?.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        let options = egui::TextureOptions {
+            magnification: key.mag_filter,
+            minification: key.min_filter,
+            wrap_mode: key.x_wrap,
+            mipmap_mode: None,
+        };
+        debug!("uploading texture {} ({}x{})", key.path, size[0], size[1]);
+        let handle = ctx.load_texture(
+            key.path.clone(),
+            egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()),
+            options,
+        );
+        let mut entries = self.entries.lock().unwrap();
+        let entry = match entries.entry(key.clone()) {
+            Entry::Occupied(entry) => {
+                self.stats.lock().unwrap().hits += 1;
+                entry.into_mut()
+            }
+            Entry::Vacant(entry) => {
+                self.stats.lock().unwrap().misses += 1;
+                entry.insert(CacheEntry {
+                    handle,
+                    references: 0,
+                    bytes: size[0] * size[1] * 4,
+                })
+            }
+        };
+        entry.references += 1;
+        Ok(CachedTexture {
+            key: key.clone(),
+            handle: entry.handle.clone(),
+        })
+    }
+
+    fn reference(&self, key: &TextureKey) -> Option<CachedTexture> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(key)?;
+        entry.references += 1;
+        Some(CachedTexture {
+            key: key.clone(),
+            handle: entry.handle.clone(),
+        })
+    }
+
+    /// Drops a reference taken by [`Self::acquire`], freeing the texture with the last one.
+    fn release(&self, key: &TextureKey) {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(key) else {
+            return;
+        };
+        entry.references -= 1;
+        if entry.references == 0 {
+            debug!("freeing texture {}", key.path);
+            entries.remove(key);
+            self.stats.lock().unwrap().evictions += 1;
+        }
+    }
+
+    pub fn stats(&self) -> TextureCacheStats {
+        let entries = self.entries.lock().unwrap();
+        TextureCacheStats {
+            textures: entries.len(),
+            references: entries.values().map(|entry| entry.references).sum(),
+            bytes: entries.values().map(|entry| entry.bytes).sum(),
+            ..*self.stats.lock().unwrap()
         }
     }
 }
@@ -78,14 +257,16 @@ pub fn set_texture_cb() {
                 }
             }
 
-            atlas_page.renderer_object().set(SpineTexture::Pending {
-                path: path.to_owned(),
-                min_filter: convert_filter(atlas_page.min_filter()),
-                mag_filter: convert_filter(atlas_page.mag_filter()),
-                x_wrap: convert_wrap(atlas_page.u_wrap()),
-                y_wrap: convert_wrap(atlas_page.v_wrap()),
-                format: convert_format(atlas_page.format()),
-            });
+            atlas_page
+                .renderer_object()
+                .set(SpineTexture::Pending(TextureKey {
+                    path: path.to_owned(),
+                    min_filter: convert_filter(atlas_page.min_filter()),
+                    mag_filter: convert_filter(atlas_page.mag_filter()),
+                    x_wrap: convert_wrap(atlas_page.u_wrap()),
+                    y_wrap: convert_wrap(atlas_page.v_wrap()),
+                    format: convert_format(atlas_page.format()),
+                }));
         },
     );
     rusty_spine::extension::set_dispose_texture_cb(
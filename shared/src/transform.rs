@@ -0,0 +1,94 @@
+use eframe::egui::{Pos2, Shape, emath::TSTransform, vec2};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Placement of an operator on screen. Operators draw around their local origin, which the
+/// host maps to `position`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub position: (f32, f32),
+    pub scale: f32,
+    pub flip_x: bool,
+    /// Operators with a higher z-index are drawn on top.
+    pub z_index: i32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: (0.0, 0.0),
+            scale: 1.0,
+            flip_x: false,
+            z_index: 0,
+        }
+    }
+}
+
+impl Transform {
+    pub fn position(&self) -> Pos2 {
+        Pos2::new(self.position.0, self.position.1)
+    }
+
+    pub fn patch(&mut self, patch: &TransformPatch) {
+        if let Some(position) = patch.position {
+            self.position = position;
+        }
+        if let Some(scale) = patch.scale {
+            self.scale = scale;
+        }
+        if let Some(flip_x) = patch.flip_x {
+            self.flip_x = flip_x;
+        }
+        if let Some(z_index) = patch.z_index {
+            self.z_index = z_index;
+        }
+    }
+
+    /// Maps a shape from operator-local into screen space. Flipping is only applied to meshes,
+    /// which is what skeletal animations are rendered as.
+    pub fn apply(&self, shape: &mut Shape) {
+        if self.flip_x {
+            mirror_x(shape);
+        }
+        shape.transform(TSTransform::new(
+            vec2(self.position.0, self.position.1),
+            self.scale,
+        ));
+    }
+}
+
+fn mirror_x(shape: &mut Shape) {
+    match shape {
+        Shape::Mesh(mesh) => Arc::make_mut(mesh)
+            .vertices
+            .iter_mut()
+            .for_each(|v| v.pos.x = -v.pos.x),
+        Shape::Vec(shapes) => shapes.iter_mut().for_each(mirror_x),
+        _ => {}
+    }
+}
+
+impl From<Transform> for TransformPatch {
+    fn from(value: Transform) -> Self {
+        Self {
+            position: Some(value.position),
+            scale: Some(value.scale),
+            flip_x: Some(value.flip_x),
+            z_index: Some(value.z_index),
+        }
+    }
+}
+
+/// Partial update of a `Transform`; unset fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransformPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<(f32, f32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flip_x: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z_index: Option<i32>,
+}
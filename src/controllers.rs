@@ -0,0 +1,70 @@
+use shared::{
+    ipc::{command_context::CommandContext, notification::Notification},
+    plugin::{Plugin, types::controller_plugin::ControllerPlugin},
+};
+use tokio::sync::broadcast::error::TryRecvError;
+use tracing::{debug, error, warn};
+
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Drives the loaded controller plugins: every tick, each controller is handed the
+/// notifications sent since the last tick, then ticked, and the commands it returns are
+/// executed like commands from a client. Ticks run on the blocking pool, since they call into
+/// the plugins and the commands take the registry locks and may block.
+pub fn spawn_driver(server: crate::ipc_handler::WebSocketServer) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut notifications = server.notifier().subscribe();
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        let dt = TICK_INTERVAL.as_secs_f32();
+        loop {
+            interval.tick().await;
+            let mut pending = Vec::new();
+            loop {
+                match notifications.try_recv() {
+                    Ok(notification) => pending.push(notification),
+                    Err(TryRecvError::Lagged(skipped)) => {
+                        warn!("Controllers missed {} notifications", skipped);
+                    }
+                    Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+                }
+            }
+
+            let mut ctx = server.command_context();
+            let ticked = tokio::task::spawn_blocking(move || tick(&mut ctx, &pending, dt)).await;
+            if let Err(e) = ticked {
+                error!("Controller tick panicked: {}", e);
+            }
+        }
+    })
+}
+
+fn tick(ctx: &mut CommandContext, pending: &[Notification], dt: f32) {
+    // Commands may need the registry lock themselves, so they run after it is released.
+    let commands: Vec<_> = {
+        let registry = ctx.plugin_registry().read().unwrap();
+        registry
+            .plugins_of::<ControllerPlugin>()
+            .into_iter()
+            .flat_map(|controller| {
+                for notification in pending {
+                    controller.notify(notification.clone());
+                }
+                let name = controller.name().to_owned();
+                controller
+                    .tick(dt)
+                    .into_iter()
+                    .map(move |command| (name.clone(), command))
+            })
+            .collect()
+    };
+    for (controller, command) in commands {
+        debug!("Controller {} issued {:?}", controller, command);
+        let response = command.execute(ctx);
+        if !response.is_success() {
+            warn!(
+                "Command from controller {} failed: {}",
+                controller, response
+            );
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use shared::{
+    ipc::command_context::CommandContext,
+    plugin::{PluginReload, types},
+};
+use std::{collections::HashMap, time::SystemTime};
+use tracing::{error, info, warn};
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Watches the files of loaded plugins and reloads a plugin when they change: the library, or
+/// the atlas, skeleton and skins of a Spine plugin.
+/// A change is only picked up once the file stayed the same for a whole poll interval, so a
+/// library that is still being written is not loaded. Polls run on the blocking pool, since
+/// they read file metadata, copy and load libraries and take the registry locks.
+pub fn spawn_watcher(server: crate::ipc_handler::WebSocketServer) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut watcher = Watcher::default();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let mut ctx = server.command_context();
+            watcher = tokio::task::spawn_blocking(move || {
+                watcher.poll(&mut ctx);
+                watcher
+            })
+            .await
+            .unwrap_or_else(|e| {
+                error!("Plugin watcher panicked: {}", e);
+                Watcher::default()
+            });
+        }
+    })
+}
+
+#[derive(Debug, Default)]
+struct Watcher {
+    /// Changed plugins waiting for their files to settle, with the time they were seen with.
+    pending: HashMap<String, SystemTime>,
+    /// Plugins whose reload failed, with the time of the files that failed to load.
+    failed: HashMap<String, SystemTime>,
+}
+
+impl Watcher {
+    fn poll(&mut self, ctx: &mut CommandContext) {
+        let changed: Vec<(String, SystemTime)> = {
+            let registry = ctx.plugin_registry().read().unwrap();
+            registry
+                .plugin_list()
+                .into_iter()
+                .filter_map(|name| {
+                    let plugin = registry.get_plugin(&name).ok()?;
+                    let modified = types::modified(plugin.path())?;
+                    (Some(modified) != plugin.modified()).then_some((name, modified))
+                })
+                .collect()
+        };
+        self.pending
+            .retain(|name, _| changed.iter().any(|(changed, _)| changed == name));
+
+        for (name, modified) in changed {
+            if self.failed.get(&name) == Some(&modified)
+                || self.pending.insert(name.clone(), modified) != Some(modified)
+            {
+                continue;
+            }
+            self.pending.remove(&name);
+            match PluginReload::run(ctx, &name) {
+                Ok(reload) => {
+                    self.failed.remove(&name);
+                    for e in &reload.errors {
+                        warn!("Failed to respawn operator of {}: {}", name, e);
+                    }
+                    info!(
+                        "reloaded plugin {} and respawned {} operators",
+                        name,
+                        reload.respawned.len()
+                    );
+                }
+                Err(e) => {
+                    error!("Failed to reload plugin {}: {}", name, e);
+                    self.failed.insert(name, modified);
+                }
+            }
+        }
+    }
+}
@@ -1,52 +1,111 @@
 use futures::{SinkExt, StreamExt as _};
 use shared::{
-    events::Event,
-    ipc::{Response, commands::Command},
-    operator::Operator,
+    events::{Delivery, Event, EventSender, PendingEvent},
+    ipc::{
+        ErrorCode, Request, Response, ResponseEnvelope,
+        command_context::CommandContext,
+        notification::{
+            NOTIFICATION_CAPACITY, Notification, NotificationEnvelope, NotificationSender,
+            Subscriptions,
+        },
+    },
+    operator::OperatorRegistry,
     plugin::PluginRegistry,
+    scheduler::Scheduler,
 };
-use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock, mpsc::Sender},
-};
-use tokio::net::TcpListener;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::{net::TcpListener, sync::broadcast::error::RecvError};
 use tokio_tungstenite::tungstenite::Message;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
+
+const SCHEDULER_TICK: std::time::Duration = std::time::Duration::from_millis(250);
+
+type WsSender = futures_util::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    Message,
+>;
 
 #[derive(Debug, Clone)]
 pub struct WebSocketServer {
     plugin_registry: Arc<RwLock<PluginRegistry>>,
-    operator_registry: Arc<RwLock<HashMap<String, Box<dyn Operator>>>>,
+    operator_registry: Arc<RwLock<OperatorRegistry>>,
+    notifier: NotificationSender,
+    scheduler: Arc<Mutex<Scheduler>>,
+    operator_tx: EventSender,
 }
 
 impl WebSocketServer {
     pub fn new(
         plugin_registry: &Arc<std::sync::RwLock<PluginRegistry>>,
-        operator_registry: &Arc<RwLock<HashMap<String, Box<dyn Operator>>>>,
+        operator_registry: &Arc<RwLock<OperatorRegistry>>,
     ) -> Self {
+        let (operator_tx, service_rx) = std::sync::mpsc::channel::<PendingEvent>();
+        let notifier = tokio::sync::broadcast::channel(NOTIFICATION_CAPACITY).0;
+
+        let plug_reg = plugin_registry.clone();
+        let op_reg = operator_registry.clone();
+        let event_notifier = notifier.clone();
+        // Waits on a blocking channel, so it gets a thread of its own rather than a worker.
+        std::thread::Builder::new()
+            .name("event-dispatch".to_owned())
+            .spawn(move || {
+                while let Ok(PendingEvent { event, reply }) = service_rx.recv() {
+                    let delivery = handle_event(event, &plug_reg, &op_reg, &event_notifier);
+                    if let Some(reply) = reply {
+                        let _ = reply.send(delivery);
+                    }
+                }
+            })
+            .expect("failed to spawn the event dispatch thread");
+
+        let scheduler: Arc<Mutex<Scheduler>> = Arc::default();
+        let due_scheduler = scheduler.clone();
+        let scheduler_tx = operator_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SCHEDULER_TICK);
+            loop {
+                interval.tick().await;
+                let due = due_scheduler.lock().unwrap().take_due(chrono::Local::now());
+                for event in due {
+                    if scheduler_tx.send(PendingEvent::new(event)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
         Self {
             plugin_registry: plugin_registry.clone(),
             operator_registry: operator_registry.clone(),
+            notifier,
+            scheduler,
+            operator_tx,
         }
     }
 
+    pub fn notifier(&self) -> &NotificationSender {
+        &self.notifier
+    }
+
+    /// A context for executing commands outside of any client connection.
+    pub fn command_context(&self) -> CommandContext {
+        CommandContext::new(
+            self.operator_registry.clone(),
+            self.plugin_registry.clone(),
+            self.operator_tx.clone(),
+            self.notifier.clone(),
+            self.scheduler.clone(),
+        )
+    }
+
     pub async fn run(&self, address: &str) -> Result<(), Box<dyn std::error::Error>> {
         let listener = TcpListener::bind(address).await?;
         tracing::info!("WebSocket server running on ws://{}", address);
-        let (operator_tx, service_rx) = std::sync::mpsc::channel::<Event>();
-
-        let op_reg = self.operator_registry.clone();
-        tokio::spawn(async move {
-            while let Ok(event) = service_rx.recv() {
-                handle_event(event, op_reg.clone());
-            }
-        });
 
         while let Ok((stream, _)) = listener.accept().await {
             let server = self.clone();
-            let op_tx = operator_tx.clone();
             tokio::spawn(async move {
-                if let Err(e) = server.handle_connection(stream, op_tx).await {
+                if let Err(e) = server.handle_connection(stream).await {
                     error!("Connection handler error: {}", e);
                 }
             });
@@ -57,14 +116,30 @@ impl WebSocketServer {
     async fn handle_connection(
         &self,
         stream: tokio::net::TcpStream,
-        operator_tx: Sender<Event>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let ws_stream = tokio_tungstenite::accept_async(stream).await?;
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+        let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
+        let mut notifications = self.notifier.subscribe();
 
-        while let Some(message) = ws_receiver.next().await {
-            self.process_message(message, &mut ws_sender, operator_tx.clone())
-                .await?;
+        loop {
+            tokio::select! {
+                message = ws_receiver.next() => {
+                    let Some(message) = message else { break };
+                    self.process_message(message, &mut ws_sender, &subscriptions)
+                        .await?;
+                }
+                notification = notifications.recv() => match notification {
+                    Ok(notification) => {
+                        self.send_notification(&mut ws_sender, &subscriptions, notification)
+                            .await?;
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Connection lagged behind, skipped {} notifications", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
         }
         Ok(())
     }
@@ -72,15 +147,12 @@ impl WebSocketServer {
     async fn process_message(
         &self,
         message: Result<Message, tokio_tungstenite::tungstenite::Error>,
-        ws_sender: &mut futures_util::stream::SplitSink<
-            tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
-            Message,
-        >,
-        operator_tx: Sender<Event>,
+        ws_sender: &mut WsSender,
+        subscriptions: &Arc<Mutex<Subscriptions>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match message {
             Ok(Message::Text(command_json)) => {
-                let response = self.execute_command(&command_json, operator_tx).await;
+                let response = self.execute_command(&command_json, subscriptions).await;
                 self.send_response(ws_sender, response).await?;
             }
             Ok(Message::Close(_)) => {
@@ -98,49 +170,108 @@ impl WebSocketServer {
 
     async fn send_response(
         &self,
-        ws_sender: &mut futures_util::stream::SplitSink<
-            tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
-            Message,
-        >,
-        response: Response,
+        ws_sender: &mut WsSender,
+        response: ResponseEnvelope,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let response_json = serde_json::to_string(&response).unwrap_or_else(|_| {
             debug!("serialisation failed: {response:?}");
             "{\"error\":\"Serialisation failed\"}".to_string()
         });
-
-        ws_sender
-            .send(Message::Text(response_json.into()))
-            .await
-            .map_err(|e| {
-                error!("Error sending response: {}", e);
-                e
-            })?;
-        Ok(())
+        send_text(ws_sender, response_json).await
     }
 
-    async fn execute_command(&self, command_json: &str, operator_tx: Sender<Event>) -> Response {
-        match Command::execute_from_json(
-            command_json,
-            &mut shared::ipc::command_context::CommandContext::new(
-                self.operator_registry.clone(),
-                self.plugin_registry.clone(),
-                operator_tx,
-            ),
-        ) {
-            Ok(response) => response,
-            Err(e) => Response::Error(format!("Command execution failed: {:?}", e)),
+    async fn send_notification(
+        &self,
+        ws_sender: &mut WsSender,
+        subscriptions: &Arc<Mutex<Subscriptions>>,
+        notification: Notification,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let subscription_ids = subscriptions.lock().unwrap().matching(&notification);
+        if subscription_ids.is_empty() {
+            return Ok(());
+        }
+        let envelope = NotificationEnvelope {
+            subscription_ids,
+            notification,
+        };
+        match serde_json::to_string(&envelope) {
+            Ok(notification_json) => send_text(ws_sender, notification_json).await,
+            Err(e) => {
+                debug!("serialisation failed: {envelope:?}: {e}");
+                Ok(())
+            }
         }
     }
+
+    /// Runs the command on the blocking pool: commands take the registry locks and may wait
+    /// for an operator to handle an event. A command that panics is answered by
+    /// [`Request::execute_from_json`] itself, so the task only fails if it is cancelled.
+    async fn execute_command(
+        &self,
+        command_json: &str,
+        subscriptions: &Arc<Mutex<Subscriptions>>,
+    ) -> ResponseEnvelope {
+        let command_json = command_json.to_owned();
+        let mut ctx = self
+            .command_context()
+            .with_subscriptions(subscriptions.clone());
+        tokio::task::spawn_blocking(move || Request::execute_from_json(&command_json, &mut ctx))
+            .await
+            .unwrap_or_else(|e| {
+                error!("Command did not run to completion: {}", e);
+                ResponseEnvelope {
+                    request_id: None,
+                    response: Response::error(ErrorCode::CommandFailed, "command was cancelled"),
+                }
+            })
+    }
 }
 
-fn handle_event(event: Event, op_registry: Arc<RwLock<HashMap<String, Box<dyn Operator>>>>) {
-    if let Some(op) = op_registry.write().unwrap().get_mut(event.operator_id()) {
-        op.event_handler(event)
-    } else {
-        debug!(
-            "Failed to designate event {:?}; Operator not in registry",
-            event
-        )
+async fn send_text(
+    ws_sender: &mut WsSender,
+    text: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    ws_sender
+        .send(Message::Text(text.into()))
+        .await
+        .map_err(|e| {
+            error!("Error sending message: {}", e);
+            e
+        })?;
+    Ok(())
+}
+
+/// Delivers an event to its operator. Events that do not arrive are announced as
+/// [`Notification::EventUndelivered`], since scheduled events have nobody else to tell.
+fn handle_event(
+    event: Event,
+    plugin_registry: &RwLock<PluginRegistry>,
+    op_registry: &RwLock<OperatorRegistry>,
+    notifier: &NotificationSender,
+) -> Delivery {
+    let plugins = plugin_registry.read().unwrap();
+    let mut operators = op_registry.write().unwrap();
+    let delivery = match event.check_accepted(&plugins, &operators) {
+        Err(reason) => Delivery::Rejected { reason },
+        Ok(()) => match operators.get_mut(event.operator_id()) {
+            Some(op) => match op.event_handler(event.clone()) {
+                Ok(()) => Delivery::Delivered,
+                Err(reason) => Delivery::Rejected { reason },
+            },
+            None => Delivery::OperatorNotFound,
+        },
     };
+    match &delivery {
+        Delivery::Delivered => {
+            let _ = notifier.send(Notification::Event { event });
+        }
+        _ => {
+            debug!("Event {:?} was not delivered: {:?}", event, delivery);
+            let _ = notifier.send(Notification::EventUndelivered {
+                event,
+                delivery: delivery.clone(),
+            });
+        }
+    }
+    delivery
 }
@@ -1,9 +1,23 @@
+mod controllers;
+mod hot_reload;
 mod ipc_handler;
+mod session;
 mod ui;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    shared::logging::init_logger().expect("failed to init logger");
-    ui::init()?;
+    let config = match shared::config::HostConfig::load(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(shared::config::Error::HelpRequested) => {
+            println!("{}", shared::config::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("arkomp: {}", e);
+            std::process::exit(2);
+        }
+    };
+    shared::logging::init_logger(&config.log).expect("failed to init logger");
+    ui::init(config)?;
     Ok(())
 }
@@ -0,0 +1,93 @@
+use shared::{
+    operator::OperatorRegistry,
+    plugin::PluginRegistry,
+    session::{self, DEFAULT_SESSION, Session},
+};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, error, info, warn};
+
+const AUTOSAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Restores the default session, if one was saved before.
+pub fn restore(server: &crate::ipc_handler::WebSocketServer) {
+    match Session::load(DEFAULT_SESSION) {
+        Ok(session) => {
+            let errors = session.restore(&mut server.command_context());
+            for e in &errors {
+                warn!("Failed to restore session entry: {}", e);
+            }
+            info!(
+                "restored session with {} plugins and {} operators ({} failures)",
+                session.plugins.len(),
+                session.operators.len(),
+                errors.len()
+            );
+        }
+        Err(session::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("no session to restore");
+        }
+        Err(e) => error!("Failed to restore session: {}", e),
+    }
+}
+
+/// Periodically writes the default session whenever the workspace changed. The workspace as
+/// it is when the task starts counts as saved, so a partially failed restore does not
+/// overwrite the session file until something changes. Capturing and writing take the
+/// registry locks and touch the disk, so they run on the blocking pool.
+pub fn spawn_autosave(
+    plugins: Arc<RwLock<PluginRegistry>>,
+    operators: Arc<RwLock<OperatorRegistry>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let (p, o) = (plugins.clone(), operators.clone());
+        let mut saved = tokio::task::spawn_blocking(move || capture(&p, &o).ok())
+            .await
+            .unwrap_or_default();
+        let mut interval = tokio::time::interval(AUTOSAVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let (p, o) = (plugins.clone(), operators.clone());
+            saved = tokio::task::spawn_blocking(move || save_if_changed(&p, &o, saved))
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Autosave panicked: {}", e);
+                    None
+                });
+        }
+    })
+}
+
+fn capture(
+    plugins: &RwLock<PluginRegistry>,
+    operators: &RwLock<OperatorRegistry>,
+) -> Result<String, session::Error> {
+    Session::capture(&plugins.read().unwrap(), &operators.read().unwrap()).to_ron()
+}
+
+/// Writes the default session if it differs from `saved`, returning what is saved now.
+fn save_if_changed(
+    plugins: &RwLock<PluginRegistry>,
+    operators: &RwLock<OperatorRegistry>,
+    saved: Option<String>,
+) -> Option<String> {
+    let Ok(current) = capture(plugins, operators) else {
+        return saved;
+    };
+    if saved.as_ref() == Some(&current) {
+        return saved;
+    }
+    match Session::path(DEFAULT_SESSION).and_then(|path| {
+        std::fs::create_dir_all(session::SESSION_DIR)?;
+        std::fs::write(path, &current)?;
+        Ok(())
+    }) {
+        Ok(()) => {
+            debug!("autosaved session");
+            Some(current)
+        }
+        Err(e) => {
+            error!("Failed to autosave session: {}", e);
+            saved
+        }
+    }
+}
@@ -2,30 +2,48 @@ use eframe::{
     NativeOptions,
     egui::{CentralPanel, Color32, Frame, ViewportBuilder},
 };
-use shared::{operator::Operator, plugin::PluginRegistry};
-use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+use shared::{
+    config::{HostConfig, WindowMode},
+    ipc::notification::Notification,
+    operator::{OperatorRegistry, take_faulted},
+    plugin::{PluginRegistry, PluginScan, types::effect_plugin::EffectPlugin},
 };
-use tracing::error;
+use std::sync::{Arc, RwLock};
+use tracing::{error, info, warn};
 
 #[derive(Debug)]
 pub struct AppState {
     socket_server: super::ipc_handler::WebSocketServer,
     _server_handle: tokio::task::JoinHandle<()>,
-    operators: Arc<RwLock<HashMap<String, Box<dyn Operator>>>>,
+    _autosave_handle: tokio::task::JoinHandle<()>,
+    _watcher_handle: Option<tokio::task::JoinHandle<()>>,
+    _controller_handle: tokio::task::JoinHandle<()>,
+    plugins: Arc<RwLock<PluginRegistry>>,
+    operators: Arc<RwLock<OperatorRegistry>>,
 }
 
 impl AppState {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let plug_reg = Arc::new(std::sync::RwLock::new(PluginRegistry::default()));
-        let op_reg = Arc::new(std::sync::RwLock::new(HashMap::default()));
+    pub fn new(_cc: &eframe::CreationContext<'_>, config: &HostConfig) -> Self {
+        let mut plugins = PluginRegistry::with_search_paths(config.plugin_dirs.clone());
+        plugins.set_copy_libraries(config.hot_reload);
+        let plug_reg = Arc::new(std::sync::RwLock::new(plugins));
+        let op_reg = Arc::new(std::sync::RwLock::new(OperatorRegistry::default()));
         let web_socket_server = super::ipc_handler::WebSocketServer::new(&plug_reg, &op_reg);
-        let op_reg_copy = op_reg.clone();
+        let scan = PluginScan::run(&mut web_socket_server.command_context());
+        for e in &scan.errors {
+            warn!("Failed to load plugin: {}", e);
+        }
+        info!("discovered {} plugins", scan.added.len());
+        super::session::restore(&web_socket_server);
+        let autosave_handle = super::session::spawn_autosave(plug_reg.clone(), op_reg.clone());
+        let watcher_handle = config
+            .hot_reload
+            .then(|| super::hot_reload::spawn_watcher(web_socket_server.clone()));
+        let controller_handle = super::controllers::spawn_driver(web_socket_server.clone());
+        let server = web_socket_server.clone();
+        let address = config.listen.clone();
         let server_handle = tokio::spawn(async move {
-            let web_socket_server =
-                super::ipc_handler::WebSocketServer::new(&plug_reg, &op_reg_copy);
-            if let Err(e) = web_socket_server.run("127.0.0.1:2887").await {
+            if let Err(e) = server.run(&address).await {
                 error!("WebSocket server error: {}", e);
             }
         });
@@ -33,6 +51,10 @@ impl AppState {
         Self {
             socket_server: web_socket_server,
             _server_handle: server_handle,
+            _autosave_handle: autosave_handle,
+            _watcher_handle: watcher_handle,
+            _controller_handle: controller_handle,
+            plugins: plug_reg,
             operators: op_reg,
         }
     }
@@ -40,11 +62,63 @@ impl AppState {
 
 impl eframe::App for AppState {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        let dt = ctx.input(|i| i.stable_dt);
+        let screen = ctx.screen_rect();
+        let bounds = ((screen.min.x, screen.min.y), (screen.max.x, screen.max.y));
         CentralPanel::default().frame(Frame::NONE).show(ctx, |ui| {
+            let plugins = self.plugins.read().unwrap();
+            let effects = plugins.plugins_of::<EffectPlugin>();
+            for effect in &effects {
+                effect.update(dt);
+                effect.render_background(ctx, ui);
+            }
             let mut operators_guard = self.operators.write().unwrap();
-            for (_, op) in operators_guard.iter_mut() {
+            for op in operators_guard.values_mut() {
+                op.update_behaviour(dt, bounds);
+                if op.update_motion(dt) {
+                    let _ = self
+                        .socket_server
+                        .notifier()
+                        .send(Notification::OperatorArrived {
+                            id: op.state().id.clone(),
+                            position: op.transform().position,
+                        });
+                }
+            }
+            let mut operators: Vec<_> = operators_guard.values_mut().collect();
+            operators.sort_by(|a, b| {
+                (a.transform().z_index, &a.state().id).cmp(&(b.transform().z_index, &b.state().id))
+            });
+            for op in operators {
                 op.render(ctx, ui);
-                op.update_animation(ctx);
+                for event in op.update_animation(ctx) {
+                    let _ = self
+                        .socket_server
+                        .notifier()
+                        .send(Notification::AnimationEvent {
+                            id: op.state().id.clone(),
+                            event,
+                        });
+                }
+            }
+            for effect in &effects {
+                effect.render_foreground(ctx, ui);
+            }
+            for op in take_faulted(&mut operators_guard) {
+                let _ = self
+                    .socket_server
+                    .notifier()
+                    .send(Notification::OperatorFaulted {
+                        id: op.state().id.clone(),
+                        plugin: op.state().plugin.clone(),
+                        error: op.fault().unwrap_or_default().to_owned(),
+                    });
+            }
+            for (name, error) in plugins.take_new_faults() {
+                let _ = self
+                    .socket_server
+                    .notifier()
+                    .send(Notification::PluginFaulted { name, error });
             }
         });
         ctx.request_repaint();
@@ -55,21 +129,29 @@ impl eframe::App for AppState {
     }
 }
 
-pub fn init() -> Result<(), eframe::Error> {
+pub fn init(config: HostConfig) -> Result<(), eframe::Error> {
+    let mut viewport = ViewportBuilder::default()
+        .with_transparent(true)
+        .with_decorations(false)
+        .with_has_shadow(false)
+        .with_mouse_passthrough(config.window.mouse_passthrough)
+        .with_taskbar(false);
+    viewport = match config.window.mode {
+        WindowMode::Fullscreen => viewport.with_fullscreen(true),
+        WindowMode::Maximized => viewport.with_maximized(true),
+        WindowMode::Windowed { width, height } => viewport.with_inner_size([width, height]),
+    };
+    if config.window.always_on_top {
+        viewport = viewport
+            .with_always_on_top()
+            .with_window_level(eframe::egui::WindowLevel::AlwaysOnTop);
+    }
     eframe::run_native(
         "Arkomp view master",
         NativeOptions {
-            viewport: ViewportBuilder::default()
-                .with_transparent(true)
-                .with_always_on_top()
-                .with_decorations(false)
-                .with_fullscreen(true)
-                .with_has_shadow(false)
-                .with_mouse_passthrough(true)
-                .with_taskbar(false)
-                .with_window_level(eframe::egui::WindowLevel::AlwaysOnTop),
+            viewport,
             ..Default::default()
         },
-        Box::new(|cc| Ok(Box::new(AppState::new(cc)))),
+        Box::new(|cc| Ok(Box::new(AppState::new(cc, &config)))),
     )
 }